@@ -12,9 +12,9 @@ fn resize_file() {
 }
 
 fn resize_mem() {
-    let pixels = vec![0;256*256*3];
+    let pixels = vec![0u8;256*256*3];
     let thumbnail = {
-        let img:VipsImage = VipsImage::from_memory(pixels, 256, 256, 3, VipsBandFormat::VIPS_FORMAT_UCHAR).unwrap();
+        let img:VipsImage = VipsImage::from_memory(pixels, 256, 256, 3).unwrap();
         img.thumbnail(234, 123, VipsSize::VIPS_SIZE_FORCE).unwrap()
     };
     thumbnail.write_to_file("black_mem_234_123.png").unwrap();
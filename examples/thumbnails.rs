@@ -0,0 +1,13 @@
+extern crate vips;
+
+use vips::*;
+
+fn main() {
+    let _instance = VipsInstance::new("thumbnails", true).unwrap();
+    // decode once, then share the pixels between every output
+    let source = VipsImage::from_file("kodim01.png").unwrap().copy_memory().unwrap();
+    for &size in &[64, 256, 1024] {
+        let thumbnail = source.thumbnail(size, size, VipsSize::VIPS_SIZE_DOWN).unwrap();
+        thumbnail.write_to_file(format!("kodim01_{}.png", size)).unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+//! Running libvips from async code without blocking the executor.
+//!
+//! `AsyncVips` owns a fixed set of worker threads. Each call queues a job and
+//! returns a `VipsFuture` that resolves once a worker has run it. At most
+//! `threads + queue` jobs are queued or running at once; further calls wait
+//! in `.await` until a slot frees up, so a burst of requests can not pile up
+//! unbounded work.
+//!
+//! Dropping a `VipsFuture` cancels its job: a job that has not started is
+//! skipped, and a running `write_to_buffer` is stopped between tiles.
+//!
+//! ```no_run
+//! # extern crate tokio;
+//! # extern crate vips;
+//! # use vips::*;
+//! # fn main() {
+//! let _instance = VipsInstance::new("server", false).unwrap();
+//! let pool = AsyncVips::new(4, 16);
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//!
+//! let jpeg = std::fs::read("kodim01.jpg").unwrap();
+//! let image = runtime.block_on(pool.from_buffer(jpeg)).unwrap();
+//! let small = runtime.block_on(pool.thumbnail(image, 128, 128, VipsSize::VIPS_SIZE_DOWN)).unwrap();
+//! let webp = runtime.block_on(pool.write_to_buffer(small, ".webp[Q=75]")).unwrap();
+//! pool.shutdown();
+//! # }
+//! ```
+
+use image::{VipsImage, CancellationToken};
+use instance::thread_shutdown;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread::{self, JoinHandle};
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore};
+use tokio::sync::oneshot;
+use ffi::VipsSize;
+
+/// Why an `AsyncVips` job did not produce a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsyncError {
+    // the libvips error message; kept as text so the error is Send
+    Vips(String),
+    // the job panicked; the worker thread survives
+    Panicked,
+    // the pool was shut down before the job ran
+    Closed,
+}
+
+impl fmt::Display for AsyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AsyncError::Vips(ref message) => write!(f, "{}", message),
+            AsyncError::Panicked => write!(f, "libvips job panicked"),
+            AsyncError::Closed => write!(f, "AsyncVips has been shut down"),
+        }
+    }
+}
+
+impl Error for AsyncError {
+    fn description(&self) -> &str {
+        "libvips job failed"
+    }
+}
+
+type Job = Box<FnOnce() + Send>;
+type Work<T> = Box<FnOnce(&CancellationToken) -> Result<T, Box<Error>> + Send>;
+type Acquire = Pin<Box<Future<Output = Result<OwnedSemaphorePermit, AcquireError>> + Send>>;
+
+struct Shared {
+    permits: Arc<Semaphore>,
+    // None once shut down
+    queue: Mutex<Option<mpsc::Sender<Job>>>,
+}
+
+/// A bounded pool of libvips worker threads for async code. See the module documentation.
+///
+/// Needs a live `VipsInstance`, and should be shut down before the instance is dropped.
+pub struct AsyncVips {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl AsyncVips {
+
+    /// Start `threads` workers, with room for `queue` more jobs waiting for a free worker.
+    pub fn new(threads: usize, queue: usize) -> AsyncVips {
+        let threads = threads.max(1);
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads).map(|i| {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("vips-async-{}", i))
+                .spawn(move || worker(receiver))
+                .expect("could not start an AsyncVips worker")
+        }).collect();
+        AsyncVips {
+            shared: Arc::new(Shared {
+                permits: Arc::new(Semaphore::new(threads + queue)),
+                queue: Mutex::new(Some(sender)),
+            }),
+            workers,
+        }
+    }
+
+    /// Run `work` on a worker thread.
+    ///
+    /// `work` is given the job's cancellation token, which is cancelled when
+    /// the returned future is dropped; write images made with
+    /// `VipsImage::with_cancellation` to stop long evaluations early.
+    pub fn run<T, F>(&self, work: F) -> VipsFuture<T>
+        where T: Send + 'static, F: FnOnce(&CancellationToken) -> Result<T, Box<Error>> + Send + 'static
+    {
+        VipsFuture {
+            state: State::Acquiring {
+                acquire: Box::pin(self.shared.permits.clone().acquire_owned()),
+                work: Box::new(work),
+            },
+            shared: self.shared.clone(),
+            token: CancellationToken::new(),
+        }
+    }
+
+    // only the header is read here; pixels are decoded when a later image is written
+    pub fn from_buffer(&self, buf: Vec<u8>) -> VipsFuture<VipsImage<'static>> {
+        self.run(move |_| VipsImage::from_owned_buffer(buf))
+    }
+
+    pub fn thumbnail(&self, image: VipsImage<'static>, width: u32, height: u32, size: VipsSize) -> VipsFuture<VipsImage<'static>> {
+        self.run(move |_| image.thumbnail(width, height, size))
+    }
+
+    /// Encode `image`, computing its pixels. This is where the work of a
+    /// pipeline happens, and it stops early if the future is dropped.
+    pub fn write_to_buffer(&self, image: VipsImage<'static>, suffix: &str) -> VipsFuture<Vec<u8>> {
+        let suffix = suffix.to_string();
+        self.run(move |token| {
+            image.with_cancellation(token)?.write_to_buffer(&suffix)
+        })
+    }
+
+    /// Finish the queued jobs and wait for the workers to exit.
+    ///
+    /// This blocks, so call it outside the async runtime, e.g. after
+    /// `block_on` has returned. Futures still waiting for a slot resolve to
+    /// `AsyncError::Closed`.
+    pub fn shutdown(mut self) {
+        self.close();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+
+    fn close(&self) {
+        self.shared.permits.close();
+        if let Ok(mut queue) = self.shared.queue.lock() {
+            queue.take();
+        }
+    }
+}
+
+// without `shutdown`, the workers finish the queue and exit in the background
+impl Drop for AsyncVips {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn worker(receiver: Arc<Mutex<mpsc::Receiver<Job>>>) {
+    loop {
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => break,
+        };
+        match job {
+            Ok(job) => job(),
+            Err(_) => break,
+        }
+    }
+    thread_shutdown();
+}
+
+enum State<T> {
+    Acquiring { acquire: Acquire, work: Work<T> },
+    Queued(oneshot::Receiver<Result<T, AsyncError>>),
+    Done,
+}
+
+/// The result of an `AsyncVips` job. Dropping it cancels the job.
+pub struct VipsFuture<T> {
+    state: State<T>,
+    shared: Arc<Shared>,
+    token: CancellationToken,
+}
+
+impl<T: Send + 'static> VipsFuture<T> {
+    // hand the job to the workers; the permit is held until it has run
+    fn submit(&self, permit: OwnedSemaphorePermit, work: Work<T>) -> Result<oneshot::Receiver<Result<T, AsyncError>>, AsyncError> {
+        let (sender, receiver) = oneshot::channel();
+        let token = self.token.clone();
+        let job: Job = Box::new(move || {
+            let _permit = permit;
+            if token.is_cancelled() {
+                return;
+            }
+            let result = match catch_unwind(AssertUnwindSafe(|| work(&token))) {
+                Ok(Ok(value)) => Ok(value),
+                Ok(Err(e)) => Err(AsyncError::Vips(e.to_string().trim().to_string())),
+                Err(_) => Err(AsyncError::Panicked),
+            };
+            let _ = sender.send(result);
+        });
+        let queue = self.shared.queue.lock().map_err(|_| AsyncError::Closed)?;
+        match *queue {
+            Some(ref queue) => queue.send(job).map_err(|_| AsyncError::Closed)?,
+            None => return Err(AsyncError::Closed),
+        }
+        Ok(receiver)
+    }
+}
+
+impl<T: Send + 'static> Future for VipsFuture<T> {
+    type Output = Result<T, AsyncError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<T, AsyncError>> {
+        let this = self.get_mut();
+        loop {
+            match mem::replace(&mut this.state, State::Done) {
+                State::Acquiring { mut acquire, work } => {
+                    let permit = match acquire.as_mut().poll(cx) {
+                        Poll::Pending => {
+                            this.state = State::Acquiring { acquire, work };
+                            return Poll::Pending;
+                        },
+                        Poll::Ready(Ok(permit)) => permit,
+                        Poll::Ready(Err(_)) => return Poll::Ready(Err(AsyncError::Closed)),
+                    };
+                    match this.submit(permit, work) {
+                        Ok(receiver) => this.state = State::Queued(receiver),
+                        Err(e) => return Poll::Ready(Err(e)),
+                    }
+                },
+                State::Queued(mut receiver) => {
+                    return match Pin::new(&mut receiver).poll(cx) {
+                        Poll::Pending => {
+                            this.state = State::Queued(receiver);
+                            Poll::Pending
+                        },
+                        Poll::Ready(Ok(result)) => Poll::Ready(result),
+                        // the worker exited without running the job
+                        Poll::Ready(Err(_)) => Poll::Ready(Err(AsyncError::Closed)),
+                    };
+                },
+                State::Done => panic!("VipsFuture polled after completion"),
+            }
+        }
+    }
+}
+
+impl<T> Drop for VipsFuture<T> {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
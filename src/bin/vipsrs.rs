@@ -0,0 +1,293 @@
+// command line front end: run single operations, print headers, batch a recipe over a directory
+
+extern crate vips;
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use vips::{Pipeline, VipsImage, VipsInstance};
+
+const USAGE: &str = "\
+usage:
+  vipsrs <operation> <in> <out> [arguments...] [--option value...]
+      run one operation, e.g. vipsrs gaussblur in.jpg out.jpg 2.0
+  vipsrs header <file>...
+      print the metadata of each file
+  vipsrs batch <recipe> <in-dir> <out-dir> [--jobs N] [--suffix .ext]
+      apply a recipe file to every file in <in-dir>
+  vipsrs list
+      list the operations vipsrs can run
+
+exit status: 0 on success, 1 if any file failed, 2 on bad usage";
+
+const EXIT_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help,
+    // the operation nickname, then input, output and any further arguments
+    Operation(Vec<String>),
+    Header(Vec<String>),
+    Batch(BatchArgs),
+    List,
+}
+
+#[derive(Debug, PartialEq)]
+struct BatchArgs {
+    recipe: String,
+    in_dir: String,
+    out_dir: String,
+    jobs: usize,
+    suffix: Option<String>,
+}
+
+// the command line without the program name; Err is a usage message
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    match args.first().map(|arg| arg.as_str()) {
+        None | Some("--help") | Some("-h") => Ok(Command::Help),
+        Some("list") => Ok(Command::List),
+        Some("header") if args.len() == 1 => Err("header needs at least one file".to_string()),
+        Some("header") => Ok(Command::Header(args[1..].to_vec())),
+        Some("batch") => parse_batch(&args[1..]).map(Command::Batch),
+        Some(_) if args.len() < 3 => Err("an operation needs an input and an output file".to_string()),
+        Some(_) => Ok(Command::Operation(args.to_vec())),
+    }
+}
+
+fn parse_batch(args: &[String]) -> Result<BatchArgs, String> {
+    let mut positional = Vec::new();
+    let mut jobs = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut suffix = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--jobs" | "--suffix" if i + 1 == args.len() => return Err(format!("{} needs a value", args[i])),
+            "--jobs" => {
+                jobs = match args[i + 1].parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err("--jobs must be a positive number".to_string()),
+                };
+                i += 1;
+            },
+            "--suffix" => {
+                suffix = Some(args[i + 1].clone());
+                i += 1;
+            },
+            arg => positional.push(arg.to_string()),
+        }
+        i += 1;
+    }
+    if positional.len() != 3 {
+        return Err("batch needs a recipe, an input directory and an output directory".to_string());
+    }
+    let out_dir = positional.pop().unwrap_or_default();
+    let in_dir = positional.pop().unwrap_or_default();
+    let recipe = positional.pop().unwrap_or_default();
+    Ok(BatchArgs { recipe, in_dir, out_dir, jobs, suffix })
+}
+
+// Ok(false) means some files of a batch failed
+fn exit_code(result: &Result<bool, Box<Error>>) -> i32 {
+    match *result {
+        Ok(true) => 0,
+        Ok(false) | Err(_) => EXIT_FAILED,
+    }
+}
+
+fn run_operation(args: &[String]) -> Result<(), Box<Error>> {
+    let mut step_args = vec![args[0].clone()];
+    step_args.extend_from_slice(&args[3..]);
+    let step = vips::recipe::parse_args(&step_args)?;
+    let image = VipsImage::from_file(args[1].as_str())?;
+    Pipeline::new().then(step).run(&image)?.write_to_file(args[2].as_str())
+}
+
+fn header(files: &[String]) -> Result<(), Box<Error>> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for file in files {
+        let image = VipsImage::from_file(file.as_str())?;
+        writeln!(out, "{}: {}x{} {} band {:?}", file, image.width(), image.height(), image.bands(), image.format())?;
+        for field in image.fields() {
+            writeln!(out, "  {}: {}", field, image.get_as_string(&field).unwrap_or_default())?;
+        }
+    }
+    Ok(())
+}
+
+fn list() -> Result<(), Box<Error>> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for op in vips::introspect::operations().iter().filter(|op| !op.is_deprecated()) {
+        writeln!(out, "{:<24} {}", op.nickname, op.description)?;
+    }
+    Ok(())
+}
+
+fn batch(args: &BatchArgs) -> Result<bool, Box<Error>> {
+    let pipeline = Pipeline::from_recipe(&fs::read_to_string(&args.recipe)?)?;
+    pipeline.validate()?;
+    let out_dir = PathBuf::from(&args.out_dir);
+    fs::create_dir_all(&out_dir)?;
+
+    let mut inputs: Vec<PathBuf> = fs::read_dir(&args.in_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    inputs.sort();
+    let total = inputs.len();
+
+    let pipeline = Arc::new(pipeline);
+    let queue = Arc::new(Mutex::new(inputs.into_iter()));
+    let done = Arc::new(AtomicUsize::new(0));
+    let failed = Arc::new(AtomicUsize::new(0));
+    let suffix = Arc::new(args.suffix.clone());
+
+    let workers: Vec<_> = (0..args.jobs.min(total.max(1))).map(|_| {
+        let (pipeline, queue, done, failed, suffix) = (pipeline.clone(), queue.clone(), done.clone(), failed.clone(), suffix.clone());
+        let out_dir = out_dir.clone();
+        thread::spawn(move || {
+            loop {
+                let input = match queue.lock().unwrap_or_else(|e| e.into_inner()).next() {
+                    Some(input) => input,
+                    None => break,
+                };
+                let output = output_path(&input, &out_dir, suffix.as_ref().as_ref().map(|s| s.as_str()));
+                let result = pipeline.run_file(input.to_string_lossy(), output.to_string_lossy());
+                let n = done.fetch_add(1, Ordering::SeqCst) + 1;
+                match result {
+                    Ok(()) => eprintln!("[{}/{}] {} -> {}", n, total, input.display(), output.display()),
+                    Err(e) => {
+                        failed.fetch_add(1, Ordering::SeqCst);
+                        eprintln!("[{}/{}] {} failed: {}", n, total, input.display(), e.to_string().trim());
+                    },
+                }
+            }
+            vips::thread_shutdown();
+        })
+    }).collect();
+    // a worker that panicked lost the file it was working on
+    for worker in workers {
+        if worker.join().is_err() {
+            failed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let failed = failed.load(Ordering::SeqCst);
+    eprintln!("{} of {} files processed, {} failed", total - failed, total, failed);
+    Ok(failed == 0)
+}
+
+// <out-dir>/<input name>, with the extension replaced by `suffix` if given
+fn output_path(input: &Path, out_dir: &Path, suffix: Option<&str>) -> PathBuf {
+    let name = input.file_name().map(PathBuf::from).unwrap_or_default();
+    match suffix {
+        Some(suffix) => {
+            let stem = name.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            out_dir.join(format!("{}{}", stem, suffix))
+        },
+        None => out_dir.join(name),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return;
+        },
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("vipsrs: {}\n\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        },
+    };
+
+    let instance = match VipsInstance::new("vipsrs", false) {
+        Ok(instance) => instance,
+        Err(e) => {
+            eprintln!("vipsrs: {}", e);
+            process::exit(EXIT_FAILED);
+        },
+    };
+    let result = match command {
+        Command::Help => Ok(true),
+        Command::Operation(args) => run_operation(&args).map(|_| true),
+        Command::Header(files) => header(&files).map(|_| true),
+        Command::Batch(args) => batch(&args),
+        Command::List => list().map(|_| true),
+    };
+    drop(instance);
+
+    if let Err(ref e) = result {
+        eprintln!("vipsrs: {}", e.to_string().trim());
+    }
+    let code = exit_code(&result);
+    if code != 0 {
+        process::exit(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse_args(&[]), Ok(Command::Help));
+        assert_eq!(parse_args(&args("--help")), Ok(Command::Help));
+        assert_eq!(parse_args(&args("list")), Ok(Command::List));
+        assert_eq!(parse_args(&args("header a.jpg b.png")), Ok(Command::Header(args("a.jpg b.png"))));
+        assert!(parse_args(&args("header")).is_err());
+        assert_eq!(parse_args(&args("gaussblur in.jpg out.jpg 2")), Ok(Command::Operation(args("gaussblur in.jpg out.jpg 2"))));
+        assert!(parse_args(&args("gaussblur in.jpg")).is_err());
+    }
+
+    #[test]
+    fn parses_batch() {
+        let batch = BatchArgs {
+            recipe: "r.recipe".to_string(),
+            in_dir: "in".to_string(),
+            out_dir: "out".to_string(),
+            jobs: 3,
+            suffix: Some(".webp".to_string()),
+        };
+        assert_eq!(parse_args(&args("batch r.recipe --jobs 3 in out --suffix .webp")), Ok(Command::Batch(batch)));
+        match parse_args(&args("batch r.recipe in out")) {
+            Ok(Command::Batch(batch)) => assert!(batch.jobs > 0 && batch.suffix.is_none()),
+            other => panic!("expected a batch, got {:?}", other),
+        }
+        assert!(parse_args(&args("batch r.recipe in")).is_err());
+        assert!(parse_args(&args("batch r.recipe in out extra")).is_err());
+        assert!(parse_args(&args("batch r.recipe in out --jobs 0")).is_err());
+        assert!(parse_args(&args("batch r.recipe in out --jobs")).is_err());
+    }
+
+    #[test]
+    fn output_paths() {
+        let out = Path::new("out");
+        assert_eq!(output_path(Path::new("in/a.jpg"), out, None), Path::new("out/a.jpg"));
+        assert_eq!(output_path(Path::new("in/a.jpg"), out, Some(".webp")), Path::new("out/a.webp"));
+        assert_eq!(output_path(Path::new("in/a.b.jpg"), out, Some("_small.png")), Path::new("out/a.b_small.png"));
+        assert_eq!(output_path(Path::new("in/noext"), out, Some(".png")), Path::new("out/noext.png"));
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(&Ok(true)), 0);
+        assert_eq!(exit_code(&Ok(false)), EXIT_FAILED);
+        assert_eq!(exit_code(&Err("failed".into())), EXIT_FAILED);
+    }
+}
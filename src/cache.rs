@@ -0,0 +1,102 @@
+//! The libvips operation cache.
+//!
+//! libvips keeps recently built operations and hands back the earlier result
+//! when an identical call is made. Loads are cached by filename, so a file
+//! changed on disk can keep returning its old pixels; use
+//! `VipsImage::from_file_with` with `no_cache` or `revalidate`,
+//! `VipsOperation::set_no_cache` or `set_revalidate`, `drop_all` or
+//! `with_cache_disabled` to get around that.
+
+use ::ffi;
+use std::sync::Mutex;
+
+// number of operations currently in the cache
+pub fn size() -> u32 {
+    unsafe { ffi::vips_cache_get_size() as u32 }
+}
+
+// same as `size`, under the libvips name
+pub fn get_size() -> u32 {
+    size()
+}
+
+// max number of operations in the cache
+pub fn max() -> i32 {
+    unsafe { ffi::vips_cache_get_max() }
+}
+
+// lowering the limit drops operations at once
+pub fn set_max(max: i32) {
+    unsafe { ffi::vips_cache_set_max(max) }
+}
+
+// max bytes of memory tracked by libvips before the cache starts dropping operations
+pub fn max_mem() -> usize {
+    unsafe { ffi::vips_cache_get_max_mem() }
+}
+
+pub fn set_max_mem(max_mem: usize) {
+    unsafe { ffi::vips_cache_set_max_mem(max_mem) }
+}
+
+// max open files before the cache starts dropping operations
+pub fn max_files() -> i32 {
+    unsafe { ffi::vips_cache_get_max_files() }
+}
+
+pub fn set_max_files(max_files: i32) {
+    unsafe { ffi::vips_cache_set_max_files(max_files) }
+}
+
+// dump the cache contents to stdout
+pub fn print() {
+    unsafe { ffi::vips_cache_print() }
+}
+
+// drop every cached operation, freeing the memory and files they hold
+pub fn drop_all() {
+    unsafe { ffi::vips_cache_drop_all() }
+}
+
+lazy_static! {
+    // open with_cache_disabled scopes, and the max to restore once the last one ends
+    static ref DISABLED: Mutex<(usize, i32)> = Mutex::new((0, 0));
+}
+
+struct DisabledScope;
+
+impl DisabledScope {
+    fn enter() -> DisabledScope {
+        let mut disabled = DISABLED.lock().unwrap_or_else(|e| e.into_inner());
+        if disabled.0 == 0 {
+            disabled.1 = max();
+            set_max(0);
+        }
+        disabled.0 += 1;
+        DisabledScope
+    }
+}
+
+impl Drop for DisabledScope {
+    fn drop(&mut self) {
+        let mut disabled = DISABLED.lock().unwrap_or_else(|e| e.into_inner());
+        disabled.0 -= 1;
+        // a max set inside the scope wins over the saved one
+        if disabled.0 == 0 && max() == 0 {
+            set_max(disabled.1);
+        }
+    }
+}
+
+/// Run `f` with the operation cache turned off, so every operation in it is
+/// built afresh and none is kept.
+///
+/// The cache is shared by the whole process: entering the scope empties it,
+/// and operations on other threads are not cached until the scope ends.
+/// Scopes may nest or overlap across threads; the previous `max` comes back
+/// when the last one ends, even if `f` panics, unless `set_max` was called
+/// with a non-zero value meanwhile, in which case that value is kept.
+pub fn with_cache_disabled<T, F: FnOnce() -> T>(f: F) -> T {
+    let _scope = DisabledScope::enter();
+    f()
+}
@@ -1,7 +1,17 @@
 // define common enums, etc.
 
 use ::ffi;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+
+// fundamental GTypes, which are macros in gtype.h
+pub(crate) const G_TYPE_BOOLEAN: ffi::GType = 5 << 2;
+pub(crate) const G_TYPE_INT: ffi::GType = 6 << 2;
+pub(crate) const G_TYPE_UINT64: ffi::GType = 11 << 2;
+pub(crate) const G_TYPE_ENUM: ffi::GType = 12 << 2;
+pub(crate) const G_TYPE_FLAGS: ffi::GType = 13 << 2;
+pub(crate) const G_TYPE_DOUBLE: ffi::GType = 15 << 2;
+pub(crate) const G_TYPE_STRING: ffi::GType = 16 << 2;
 
 
 pub fn current_error() -> String {
@@ -9,4 +19,13 @@ pub fn current_error() -> String {
         CStr::from_ptr(ffi::vips_error_buffer())
     };
     msg.to_str().unwrap().to_string()
-}
\ No newline at end of file
+}
+
+// push a message onto the libvips error buffer, e.g. from inside a callback
+pub(crate) fn set_error(domain: &str, msg: &str) {
+    let domain = CString::new(domain).unwrap_or_default();
+    let msg = CString::new(msg.replace('\0', "")).unwrap_or_default();
+    unsafe {
+        ffi::vips_error(domain.as_ptr(), "%s\0".as_ptr() as *const c_char, msg.as_ptr());
+    }
+}
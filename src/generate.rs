@@ -0,0 +1,209 @@
+// partial images implemented in rust
+
+use ::ffi;
+use ffi::{VipsRect, VipsBandFormat, VipsDemandStyle};
+use image::VipsImage;
+use region::{VipsRegion, RegionMut};
+use common::{current_error, set_error};
+use std::error::Error;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::null_mut;
+
+/// The shape of a generated image.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Header {
+    pub width: u32,
+    pub height: u32,
+    pub bands: u32,
+    pub format: VipsBandFormat,
+}
+
+/// Computes the pixels of a partial image one rectangle at a time.
+///
+/// libvips calls `generate` from its worker threads, each thread with its own
+/// set of input regions and its own `State`, so implementations must be
+/// `Send + Sync`.
+pub trait Generator: Send + Sync {
+    /// Scratch data for one libvips thread, such as a buffer reused between
+    /// calls to `generate`. Use `()` if none is needed.
+    type State: Default + Send;
+
+    // the output header; by default the same as the first input
+    fn header(&self, input: Header) -> Header {
+        input
+    }
+
+    // the input area needed to compute `out`; widen it for neighbourhood filters
+    fn input_area(&self, out: &VipsRect) -> VipsRect {
+        *out
+    }
+
+    // make the state of a thread when it starts working on the image
+    fn start(&self) -> Result<Self::State, Box<Error>> {
+        Ok(<Self::State as Default>::default())
+    }
+
+    // release the state of a thread when it is done with the image
+    fn stop(&self, _state: Self::State) {}
+
+    /// Fill the valid area of `out`. Every region in `input` has been prepared
+    /// with `input_area(&out.valid())`.
+    fn generate(&self, out: &mut RegionMut, input: &[VipsRegion], state: &mut Self::State) -> Result<(), Box<Error>>;
+}
+
+struct GenerateState<G> {
+    generator: G,
+    // null-terminated, each one reffed for the lifetime of the output
+    inputs: Vec<*mut ffi::VipsImage>,
+}
+
+impl<G> Drop for GenerateState<G> {
+    fn drop(&mut self) {
+        for &im in self.inputs.iter().filter(|im| !im.is_null()) {
+            unsafe {
+                ffi::g_object_unref(im as *mut c_void);
+            }
+        }
+    }
+}
+
+// what one libvips thread works with: a region on each input, and the generator state
+struct Sequence<S> {
+    regions: Vec<VipsRegion<'static>>,
+    state: S,
+}
+
+// errors and panics become a libvips error and a null sequence, which fails the evaluation
+unsafe extern "C" fn generate_start<G: Generator>(_out: *mut ffi::VipsImage, a: *mut c_void, _b: *mut c_void) -> *mut c_void {
+    let state = &*(a as *const GenerateState<G>);
+    let result = catch_unwind(AssertUnwindSafe(|| -> Result<Sequence<G::State>, Box<Error>> {
+        let mut regions = Vec::new();
+        for &im in state.inputs.iter().take_while(|im| !im.is_null()) {
+            let region = ffi::vips_region_new(im);
+            if region.is_null() {
+                return Err(current_error().into());
+            }
+            regions.push(VipsRegion::from_raw(region));
+        }
+        Ok(Sequence { regions, state: state.generator.start()? })
+    }));
+    match result {
+        Ok(Ok(seq)) => Box::into_raw(Box::new(seq)) as *mut c_void,
+        Ok(Err(e)) => {
+            set_error("generate", &e.to_string());
+            null_mut()
+        },
+        Err(_) => {
+            set_error("generate", "generator panicked");
+            null_mut()
+        },
+    }
+}
+
+unsafe extern "C" fn generate_region<G: Generator>(out: *mut ffi::VipsRegion, seq: *mut c_void, a: *mut c_void, _b: *mut c_void, _stop: *mut ffi::gboolean) -> c_int {
+    let state = &*(a as *const GenerateState<G>);
+    let seq = &mut *(seq as *mut Sequence<G::State>);
+    let mut out = RegionMut::from_raw(out);
+
+    let result = catch_unwind(AssertUnwindSafe(|| -> Result<(), Box<Error>> {
+        let area = state.generator.input_area(&out.valid());
+        for region in seq.regions.iter_mut() {
+            region.prepare(&area)?;
+        }
+        state.generator.generate(&mut out, &seq.regions, &mut seq.state)
+    }));
+    match result {
+        Ok(Ok(())) => 0,
+        Ok(Err(e)) => {
+            set_error("generate", &e.to_string());
+            -1
+        },
+        Err(_) => {
+            set_error("generate", "generator panicked");
+            -1
+        },
+    }
+}
+
+unsafe extern "C" fn generate_stop<G: Generator>(seq: *mut c_void, a: *mut c_void, _b: *mut c_void) -> c_int {
+    let state = &*(a as *const GenerateState<G>);
+    let seq = Box::from_raw(seq as *mut Sequence<G::State>);
+    match catch_unwind(AssertUnwindSafe(|| state.generator.stop(seq.state))) {
+        Ok(()) => 0,
+        Err(_) => {
+            set_error("generate", "generator panicked");
+            -1
+        },
+    }
+}
+
+unsafe extern "C" fn generate_postclose<G: Generator>(_ptr: *mut ffi::VipsImage, user_data: *mut c_void) {
+    drop(Box::from_raw(user_data as *mut GenerateState<G>));
+}
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── GENERATE ───────────────────────────────────────────────────────────────────
+    //
+
+    /// Make a partial image whose pixels are computed on demand by `generator`.
+    ///
+    /// Nothing is computed here; pixels are generated when a downstream
+    /// operation or a save asks for them, in parallel on libvips' threads.
+    pub fn generate<G: Generator + 'a>(inputs: &[&VipsImage<'a>], generator: G) -> Result<VipsImage<'a>, Box<Error>> {
+        if inputs.is_empty() {
+            return Err("generate needs at least one input image".into());
+        }
+        let out = VipsImage::new()?;
+
+        let mut array: Vec<*mut ffi::VipsImage> = inputs.iter().map(|im| im.c).collect();
+        array.push(null_mut());
+        unsafe {
+            if ffi::vips_image_pipeline_array(out.c, VipsDemandStyle::VIPS_DEMAND_STYLE_SMALLTILE, array.as_mut_ptr()) != 0 {
+                return Err(current_error().into());
+            }
+
+            let header = generator.header(Header {
+                width: (*out.c).Xsize as u32,
+                height: (*out.c).Ysize as u32,
+                bands: (*out.c).Bands as u32,
+                format: (*out.c).BandFmt,
+            });
+            (*out.c).Xsize = header.width as c_int;
+            (*out.c).Ysize = header.height as c_int;
+            (*out.c).Bands = header.bands as c_int;
+            (*out.c).BandFmt = header.format;
+
+            for &im in array.iter().filter(|im| !im.is_null()) {
+                ffi::g_object_ref(im as *mut c_void);
+            }
+        }
+
+        let state = Box::new(GenerateState { generator, inputs: array });
+        let raw = Box::into_raw(state) as *mut c_void;
+
+        unsafe {
+            let callback: unsafe extern "C" fn() = ::std::mem::transmute(generate_postclose::<G> as *const());
+            ffi::g_signal_connect_data(
+                out.c as *mut c_void, "postclose\0".as_ptr() as *const c_char,
+                Some(callback),
+                raw,
+                None, ffi::GConnectFlags::G_CONNECT_AFTER);
+
+            let ret = ffi::vips_image_generate(
+                out.c,
+                Some(generate_start::<G>),
+                Some(generate_region::<G>),
+                Some(generate_stop::<G>),
+                raw,
+                null_mut());
+            if ret != 0 {
+                return Err(current_error().into());
+            }
+        }
+
+        Ok(out)
+    }
+}
@@ -0,0 +1,37 @@
+// conversions to and from ndarray
+
+use ndarray::{Array3, ArrayView3};
+use pixel::VipsPixel;
+use std::error::Error;
+use super::VipsImage;
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── NDARRAY ────────────────────────────────────────────────────────────────────
+    //
+
+    // render the image into a height x width x bands array
+    pub fn to_ndarray<T: VipsPixel>(&self) -> Result<Array3<T>, Box<Error>> {
+        let shape = (self.height() as usize, self.width() as usize, self.bands() as usize);
+        let data = self.to_vec::<T>()?;
+        Ok(Array3::from_shape_vec(shape, data)?)
+    }
+
+    /// Borrow a height x width x bands array as an image.
+    ///
+    /// The array must be contiguous in standard (row major) layout, which is
+    /// what `Array3::zeros` and `from_shape_vec` give you. Transposed or sliced
+    /// views are refused; call `as_standard_layout()` on them first.
+    pub fn from_ndarray<T: VipsPixel>(array: ArrayView3<'a, T>) -> Result<VipsImage<'a>, Box<Error>> {
+        let (height, width, bands) = array.dim();
+        if bands > u8::MAX as usize || width > u32::MAX as usize || height > u32::MAX as usize {
+            return Err(format!("array of {}x{}x{} is too large for an image", height, width, bands).into());
+        }
+        let data = match array.to_slice() {
+            Some(data) => data,
+            None => return Err("array is not contiguous in standard layout".into()),
+        };
+        VipsImage::from_slice(data, width as u32, height as u32, bands as u8, T::FORMAT)
+    }
+}
@@ -0,0 +1,150 @@
+use ffi;
+use std::error::Error;
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr::null_mut;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use common::current_error;
+use super::VipsImage;
+
+// object data marking an image as killed from rust. libvips clears `kill` once
+// it has stopped an evaluation, so the flag alone can not tell why a write failed.
+// the marker is cleared in turn when that failure is reported
+const KILLED: &[u8] = b"vips-rs-killed\0";
+
+/// The error returned when evaluation was stopped with `set_kill` or a
+/// `CancellationToken`. Recognise it with `err.downcast_ref::<Cancelled>()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "evaluation was cancelled")
+    }
+}
+
+impl Error for Cancelled {
+    fn description(&self) -> &str {
+        "evaluation was cancelled"
+    }
+}
+
+struct TokenState {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+}
+
+/// A shareable flag that stops the evaluation of images made with
+/// `VipsImage::with_cancellation`.
+///
+/// Clones share the same flag, so one can be kept by a supervisor thread
+/// while another is given to the thread doing the work.
+#[derive(Clone)]
+pub struct CancellationToken {
+    state: Arc<TokenState>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken {
+            state: Arc::new(TokenState { cancelled: AtomicBool::new(false), deadline: None }),
+        }
+    }
+
+    // a token that cancels itself once `timeout` has passed
+    pub fn with_timeout(timeout: Duration) -> CancellationToken {
+        CancellationToken {
+            state: Arc::new(TokenState { cancelled: AtomicBool::new(false), deadline: Some(Instant::now() + timeout) }),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+            || self.state.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> CancellationToken {
+        CancellationToken::new()
+    }
+}
+
+// set or clear the kill flag of a libvips image, remembering that rust asked for it
+unsafe fn kill(c: *mut ffi::VipsImage, kill: bool) {
+    let marker = if kill { c as *mut c_void } else { null_mut() };
+    ffi::g_object_set_data(c as *mut ffi::GObject, KILLED.as_ptr() as *const c_char, marker);
+    (*c).kill = kill as c_int;
+}
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── CANCELLATION ───────────────────────────────────────────────────────────────
+    //
+
+    /// Ask libvips to stop computing this image. The running evaluation, or
+    /// the next one, fails with `Cancelled`; libvips then clears the kill, so
+    /// later evaluations run normally. `set_kill(false)` withdraws a kill that
+    /// has not stopped anything yet.
+    ///
+    /// The flag is on the libvips image, which the operation cache may have
+    /// handed to other callers too, so it stops their evaluations as well.
+    /// Use `with_cancellation` to stop just one write.
+    pub fn set_kill(&self, kill: bool) {
+        unsafe { self::kill(self.c, kill) }
+    }
+
+    // whether a kill from `set_kill` or a cancellation token is still pending
+    pub fn is_killed(&self) -> bool {
+        unsafe { !ffi::g_object_get_data(self.c as *mut ffi::GObject, KILLED.as_ptr() as *const c_char).is_null() }
+    }
+
+    // whether a failed evaluation was stopped by a kill, which is then done with
+    pub(crate) fn take_kill(&self) -> bool {
+        let killed = self.is_killed();
+        if killed {
+            unsafe { kill(self.c, false) }
+        }
+        killed
+    }
+
+    /// A private image with the pixels of this one, whose evaluation stops
+    /// once `token` is cancelled.
+    ///
+    /// The token is polled from the `eval` signal, so it takes effect between
+    /// tiles of a running `write_to_*` call. Only writes of the returned image
+    /// are cancelled; this image and its clones are left alone.
+    pub fn with_cancellation(&self, token: &CancellationToken) -> Result<VipsImage<'a>, Box<Error>> {
+        let mut private = VipsImage::new()?;
+        if unsafe { ffi::vips_image_write(self.c, private.c) } != 0 {
+            return Err(current_error().into());
+        }
+        private.set_progress(true)?;
+        let token = token.clone();
+        // the handler is dropped with the private image, so the pointer stays valid
+        let c = private.c as usize;
+        private.on_eval(move |_| {
+            if token.is_cancelled() {
+                unsafe { kill(c as *mut ffi::VipsImage, true) }
+            }
+        })?;
+        Ok(private)
+    }
+
+    // the error for a failed evaluation of self
+    pub(crate) fn eval_error(&self) -> Box<Error> {
+        if self.take_kill() {
+            unsafe { ffi::vips_error_clear() };
+            Box::new(Cancelled)
+        } else {
+            current_error().into()
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use ffi;
+use ffi::{VipsBandFormat, VipsInterpretation};
+use operation::VipsOperation;
+use std::error::Error;
+use std::f64;
+use std::fmt;
+use super::VipsImage;
+
+/// How two images differ, from `VipsImage::diff`.
+pub struct DiffReport<'a> {
+    // largest absolute difference of any band of any pixel
+    pub max_abs: f64,
+    // absolute difference averaged over every band of every pixel
+    pub mean_abs: f64,
+    // mean squared error of each band
+    pub mse: Vec<f64>,
+    /// Peak signal to noise ratio in dB over all bands, infinite for identical
+    /// images. The peak is the width of the range of the band format, so 255
+    /// for both UCHAR and CHAR, or 1.0 for float images.
+    pub psnr: f64,
+    // mean and largest CIEDE2000 difference, for colour images only
+    pub mean_de00: Option<f64>,
+    pub max_de00: Option<f64>,
+    /// One band image of the difference at each pixel, for viewing: dE00 for
+    /// colour images, otherwise the absolute difference averaged over bands.
+    pub heatmap: VipsImage<'a>,
+}
+
+impl<'a> DiffReport<'a> {
+    pub fn is_identical(&self) -> bool {
+        self.max_abs == 0.0
+    }
+}
+
+impl<'a> fmt::Display for DiffReport<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "max abs {}, mean abs {:.4}, PSNR {:.2} dB, MSE {:?}", self.max_abs, self.mean_abs, self.psnr, self.mse)?;
+        if let (Some(mean), Some(max)) = (self.mean_de00, self.max_de00) {
+            write!(f, ", dE00 mean {:.4} max {:.4}", mean, max)?;
+        }
+        Ok(())
+    }
+}
+
+// the PSNR peak: the width of the range of a band format. signed formats have
+// the same width as unsigned ones, e.g. 255 for -128..127
+fn peak(format: VipsBandFormat) -> f64 {
+    match format {
+        VipsBandFormat::VIPS_FORMAT_UCHAR | VipsBandFormat::VIPS_FORMAT_CHAR => 255.0,
+        VipsBandFormat::VIPS_FORMAT_USHORT | VipsBandFormat::VIPS_FORMAT_SHORT => 65535.0,
+        VipsBandFormat::VIPS_FORMAT_UINT | VipsBandFormat::VIPS_FORMAT_INT => 4294967295.0,
+        _ => 1.0,
+    }
+}
+
+fn is_colour(image: &VipsImage) -> bool {
+    let interpretation = unsafe { ffi::vips_image_guess_interpretation(image.c) };
+    (image.bands() == 3 || image.bands() == 4) && matches!(interpretation,
+        VipsInterpretation::VIPS_INTERPRETATION_sRGB
+        | VipsInterpretation::VIPS_INTERPRETATION_RGB
+        | VipsInterpretation::VIPS_INTERPRETATION_RGB16
+        | VipsInterpretation::VIPS_INTERPRETATION_scRGB
+        | VipsInterpretation::VIPS_INTERPRETATION_LAB
+        | VipsInterpretation::VIPS_INTERPRETATION_LABS
+        | VipsInterpretation::VIPS_INTERPRETATION_LCH
+        | VipsInterpretation::VIPS_INTERPRETATION_CMC
+        | VipsInterpretation::VIPS_INTERPRETATION_XYZ
+        | VipsInterpretation::VIPS_INTERPRETATION_YXY
+        | VipsInterpretation::VIPS_INTERPRETATION_HSV)
+}
+
+// run an operation whose inputs are images and whose result is "out"
+fn call<'a>(nickname: &str, inputs: &[(&str, &VipsImage<'a>)], ints: &[(&str, i32)]) -> Result<VipsImage<'a>, Box<Error>> {
+    let mut op = VipsOperation::new(nickname)?;
+    for &(name, image) in inputs {
+        op.set_image(name, image)?;
+    }
+    for &(name, value) in ints {
+        op.set_int(name, value)?;
+    }
+    op.build()?;
+    op.get_image("out")
+}
+
+// vips_stats as rows of min, max, sum, sum2, mean, deviation, ...: all bands first, then each band
+fn stats(image: &VipsImage) -> Result<Vec<Vec<f64>>, Box<Error>> {
+    let matrix = call("stats", &[("in", image)], &[])?;
+    let columns = matrix.width() as usize;
+    Ok(matrix.to_vec::<f64>()?.chunks(columns).map(|row| row.to_vec()).collect())
+}
+
+const MAX: usize = 1;
+const SUM2: usize = 3;
+const MEAN: usize = 4;
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── COMPARISON ─────────────────────────────────────────────────────────────────
+    //
+
+    /// Compare with an image of the same size and number of bands.
+    ///
+    /// The difference is `self - other` computed by libvips, so both images
+    /// are evaluated. Colour images (3 or 4 bands with a colour
+    /// interpretation) also get CIEDE2000 figures, ignoring any alpha band.
+    pub fn diff(&self, other: &VipsImage<'a>) -> Result<DiffReport<'a>, Box<Error>> {
+        if (self.width(), self.height(), self.bands()) != (other.width(), other.height(), other.bands()) {
+            return Err(format!("can not compare a {}x{} {} band image with a {}x{} {} band image",
+                self.width(), self.height(), self.bands(), other.width(), other.height(), other.bands()).into());
+        }
+
+        let difference = call("subtract", &[("left", self), ("right", other)], &[])?;
+        let abs = call("abs", &[("in", &difference)], &[])?;
+        let abs_stats = stats(&abs)?;
+        let pixels = self.width() as f64 * self.height() as f64;
+        let mse: Vec<f64> = stats(&difference)?[1..].iter().map(|band| band[SUM2] / pixels).collect();
+        let mean_mse = mse.iter().sum::<f64>() / mse.len() as f64;
+        let peak = peak(self.format());
+        let psnr = if mean_mse == 0.0 { f64::INFINITY } else { 10.0 * (peak * peak / mean_mse).log10() };
+
+        let (heatmap, mean_de00, max_de00) = if is_colour(self) && is_colour(other) {
+            let colour = |image: &VipsImage<'a>| -> Result<VipsImage<'a>, Box<Error>> {
+                match image.bands() {
+                    4 => call("extract_band", &[("in", image)], &[("band", 0), ("n", 3)]),
+                    _ => Ok(image.clone()),
+                }
+            };
+            let de00 = call("dE00", &[("left", &colour(self)?), ("right", &colour(other)?)], &[])?;
+            let de00_stats = stats(&de00)?;
+            (de00, Some(de00_stats[0][MEAN]), Some(de00_stats[0][MAX]))
+        } else if self.bands() > 1 {
+            (call("bandmean", &[("in", &abs)], &[])?, None, None)
+        } else {
+            (abs, None, None)
+        };
+
+        Ok(DiffReport {
+            max_abs: abs_stats[0][MAX],
+            mean_abs: abs_stats[0][MEAN],
+            mse,
+            psnr,
+            mean_de00,
+            max_de00,
+            heatmap,
+        })
+    }
+}
+
+/// Assert that two images differ by at most `tolerance` in any band of any
+/// pixel, printing the `DiffReport` if they do not.
+///
+/// ```no_run
+/// # #[macro_use] extern crate vips;
+/// # use vips::*;
+/// # fn main() {
+/// # let _instance = VipsInstance::new("diff", false).unwrap();
+/// let expected = VipsImage::from_file("expected.png").unwrap();
+/// let actual = VipsImage::from_file("actual.png").unwrap();
+/// assert_images_similar!(actual, expected, 2.0);
+/// assert_images_similar!(actual, expected, 2.0, "render of {}", "actual.png");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_images_similar {
+    ($left:expr, $right:expr, $tolerance:expr) => {
+        assert_images_similar!($left, $right, $tolerance, "images differ")
+    };
+    ($left:expr, $right:expr, $tolerance:expr, $($message:tt)+) => {
+        match $left.diff(&$right) {
+            Ok(report) => {
+                if !(report.max_abs <= $tolerance as f64) {
+                    panic!("{}: max abs difference {} is over the tolerance {} ({})",
+                        format_args!($($message)+), report.max_abs, $tolerance, report);
+                }
+            },
+            Err(e) => panic!("{}: could not compare: {}", format_args!($($message)+), e),
+        }
+    };
+}
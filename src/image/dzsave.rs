@@ -0,0 +1,116 @@
+use ffi::{VipsForeignDzDepth, VipsForeignDzContainer, VipsAngle};
+use std::error::Error;
+use operation::VipsOperation;
+use super::{Cancelled, VipsImage};
+
+/// The directory layout of a tile pyramid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DzLayout {
+    DeepZoom,
+    Zoomify,
+    Google,
+    // needs libvips 8.10
+    #[cfg(feature = "vips-8-10")]
+    Iiif,
+}
+
+impl DzLayout {
+    // the VipsForeignDzLayout value
+    fn value(&self) -> i32 {
+        match *self {
+            DzLayout::DeepZoom => 0,
+            DzLayout::Zoomify => 1,
+            DzLayout::Google => 2,
+            #[cfg(feature = "vips-8-10")]
+            DzLayout::Iiif => 3,
+        }
+    }
+}
+
+/// Options for `dzsave`. The defaults match libvips.
+#[derive(Debug, Clone)]
+pub struct DzOptions {
+    pub layout: DzLayout,
+    // how deep to make the pyramid
+    pub depth: VipsForeignDzDepth,
+    // a directory tree, or a single zip file
+    pub container: VipsForeignDzContainer,
+    pub tile_size: u32,
+    // pixels shared by neighbouring tiles
+    pub overlap: u32,
+    // tile file suffix, with save options, e.g. ".jpg[Q=90]"
+    pub suffix: String,
+    // centre the image in the tile
+    pub centre: bool,
+    pub angle: VipsAngle,
+    // skip tiles whose pixels are all within this distance of the background; None keeps every tile
+    pub skip_blanks: Option<i32>,
+    // base URL written to the IIIF info.json
+    pub id: Option<String>,
+}
+
+impl Default for DzOptions {
+    fn default() -> DzOptions {
+        DzOptions {
+            layout: DzLayout::DeepZoom,
+            depth: VipsForeignDzDepth::VIPS_FOREIGN_DZ_DEPTH_ONEPIXEL,
+            container: VipsForeignDzContainer::VIPS_FOREIGN_DZ_CONTAINER_FS,
+            tile_size: 254,
+            overlap: 1,
+            suffix: ".jpeg".to_string(),
+            centre: false,
+            angle: VipsAngle::VIPS_ANGLE_D0,
+            skip_blanks: None,
+            id: None,
+        }
+    }
+}
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── DZSAVE ─────────────────────────────────────────────────────────────────────
+    //
+
+    /// Write a tile pyramid for DeepZoom, Zoomify, Google Maps or IIIF viewers.
+    ///
+    /// `path` is the base name: the DeepZoom layout writes `path.dzi` and `path_files/`,
+    /// and the zip container writes `path.zip`.
+    pub fn dzsave(&self, path: &str, options: &DzOptions) -> Result<(), Box<Error>> {
+        let mut op = VipsOperation::new("dzsave")?;
+        op.set_image("in", self)?;
+        op.set_string("filename", path)?;
+        self.run_dzsave(op, options, options.container).map(|_| ())
+    }
+
+    // the pyramid as a zip file in memory; `options.container` is ignored
+    #[cfg(feature = "vips-8-10")]
+    pub fn dzsave_buffer(&self, options: &DzOptions) -> Result<Vec<u8>, Box<Error>> {
+        let mut op = VipsOperation::new("dzsave_buffer")?;
+        op.set_image("in", self)?;
+        let op = self.run_dzsave(op, options, VipsForeignDzContainer::VIPS_FOREIGN_DZ_CONTAINER_ZIP)?;
+        op.get_blob("buffer")
+    }
+
+    fn run_dzsave<'o>(&self, mut op: VipsOperation<'o>, options: &DzOptions, container: VipsForeignDzContainer) -> Result<VipsOperation<'o>, Box<Error>> {
+        op.set_int("layout", options.layout.value())?;
+        op.set_int("depth", options.depth as i32)?;
+        op.set_int("container", container as i32)?;
+        op.set_int("tile_size", options.tile_size as i32)?;
+        op.set_int("overlap", options.overlap as i32)?;
+        op.set_string("suffix", &options.suffix)?;
+        op.set_bool("centre", options.centre)?;
+        op.set_int("angle", options.angle as i32)?;
+        if let Some(skip_blanks) = options.skip_blanks {
+            op.set_int("skip_blanks", skip_blanks)?;
+        }
+        if let Some(ref id) = options.id {
+            op.set_string("id", id)?;
+        }
+        match op.build() {
+            Ok(()) => Ok(op),
+            Err(_) if self.take_kill() => Err(Box::new(Cancelled)),
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use ffi;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr::null_mut;
+use std::slice;
+use super::VipsImage;
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── METADATA ───────────────────────────────────────────────────────────────────
+    //
+
+    // metadata and signal handlers are shared with clones and downstream images,
+    // so set them on a fresh lazy copy when self is not the only reference
+    pub(crate) fn make_header_unique(&mut self) -> Result<(), Box<Error>> {
+        if self.ref_count() > 1 {
+            let mut out: *mut ffi::VipsImage = null_mut();
+            let ret = unsafe { ffi::vips_copy(self.c, &mut out, ::std::ptr::null() as *const ::std::os::raw::c_char) };
+            *self = super::result_with_ret(out, ret)?;
+        }
+        Ok(())
+    }
+
+    pub fn has_field(&self, name: &str) -> bool {
+        match CString::new(name) {
+            Ok(name) => unsafe { ffi::vips_image_get_typeof(self.c, name.as_ptr()) != 0 },
+            Err(_) => false,
+        }
+    }
+
+    // names of every metadata field, including the built-in ones such as "width"
+    pub fn fields(&self) -> Vec<String> {
+        unsafe {
+            let fields = ffi::vips_image_get_fields(self.c);
+            let mut names = Vec::new();
+            let mut p = fields;
+            while !(*p).is_null() {
+                names.push(CStr::from_ptr(*p).to_string_lossy().into_owned());
+                p = p.offset(1);
+            }
+            ffi::g_strfreev(fields);
+            names
+        }
+    }
+
+    // any field formatted as text, the way `vipsheader -a` prints it
+    pub fn get_as_string(&self, name: &str) -> Option<String> {
+        if !self.has_field(name) {
+            return None;
+        }
+        let name = CString::new(name).ok()?;
+        let mut out: *mut c_char = null_mut();
+        unsafe {
+            if ffi::vips_image_get_as_string(self.c, name.as_ptr(), &mut out) != 0 || out.is_null() {
+                return None;
+            }
+            let value = CStr::from_ptr(out).to_string_lossy().into_owned();
+            ffi::g_free(out as *mut c_void);
+            Some(value)
+        }
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i32> {
+        if !self.has_field(name) {
+            return None;
+        }
+        let name = CString::new(name).ok()?;
+        let mut out: c_int = 0;
+        match unsafe { ffi::vips_image_get_int(self.c, name.as_ptr(), &mut out) } {
+            0 => Some(out),
+            _ => None,
+        }
+    }
+
+    pub fn set_int(&mut self, name: &str, value: i32) -> Result<(), Box<Error>> {
+        let name = CString::new(name)?;
+        self.make_header_unique()?;
+        unsafe { ffi::vips_image_set_int(self.c, name.as_ptr(), value) }
+        Ok(())
+    }
+
+    pub fn get_int_array(&self, name: &str) -> Option<Vec<i32>> {
+        if !self.has_field(name) {
+            return None;
+        }
+        let name = CString::new(name).ok()?;
+        unsafe {
+            let mut value: ffi::GValue = mem::zeroed();
+            if ffi::vips_image_get(self.c, name.as_ptr(), &mut value) != 0 {
+                return None;
+            }
+            let array = if value.g_type == ffi::vips_array_int_get_type() {
+                let mut n: c_int = 0;
+                let data = ffi::vips_value_get_array_int(&value, &mut n);
+                Some(slice::from_raw_parts(data, n as usize).to_vec())
+            } else {
+                None
+            };
+            ffi::g_value_unset(&mut value);
+            array
+        }
+    }
+
+    pub fn set_int_array(&mut self, name: &str, values: &[i32]) -> Result<(), Box<Error>> {
+        let name = CString::new(name)?;
+        self.make_header_unique()?;
+        unsafe {
+            let mut value: ffi::GValue = mem::zeroed();
+            ffi::g_value_init(&mut value, ffi::vips_array_int_get_type());
+            ffi::vips_value_set_array_int(&mut value, values.as_ptr(), values.len() as c_int);
+            ffi::vips_image_set(self.c, name.as_ptr(), &mut value);
+            ffi::g_value_unset(&mut value);
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), Box<Error>> {
+        let name = CString::new(name)?;
+        self.make_header_unique()?;
+        unsafe { ffi::vips_image_remove(self.c, name.as_ptr()) };
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+// conversions to and from the `image` crate
+
+use ffi;
+use ffi::{VipsBandFormat, VipsInterpretation};
+use image_crate::{DynamicImage, ImageBuffer, Pixel};
+use pixel::VipsPixel;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::os::raw::c_char;
+use std::ptr::{null, null_mut};
+use super::{VipsImage, result_with_ret};
+
+// the image crate keeps float samples in 0..1, while libvips expects float
+// sRGB and B_W in 0..255 like 8 bit images, so floats are scaled on the way
+// in and out rather than tagged as linear scRGB
+const FLOAT_SCALE: f64 = 255.0;
+
+fn is_float(format: VipsBandFormat) -> bool {
+    format == VipsBandFormat::VIPS_FORMAT_FLOAT || format == VipsBandFormat::VIPS_FORMAT_DOUBLE
+}
+
+// a lazy `image * a`, which keeps the format and interpretation of float images
+fn scale<'a>(image: &VipsImage<'a>, a: f64) -> Result<VipsImage<'a>, Box<Error>> {
+    let mut out: *mut ffi::VipsImage = null_mut();
+    let ret = unsafe { ffi::vips_linear1(image.c, &mut out, a, 0.0, null() as *const c_char) };
+    result_with_ret(out, ret)
+}
+
+// what libvips should assume the bands mean, so that savers treat the pixels correctly
+fn interpretation(format: VipsBandFormat, bands: u8) -> VipsInterpretation {
+    match (format, bands) {
+        (VipsBandFormat::VIPS_FORMAT_USHORT, 1) | (VipsBandFormat::VIPS_FORMAT_USHORT, 2) => VipsInterpretation::VIPS_INTERPRETATION_GREY16,
+        (VipsBandFormat::VIPS_FORMAT_USHORT, _) => VipsInterpretation::VIPS_INTERPRETATION_RGB16,
+        (_, 1) | (_, 2) => VipsInterpretation::VIPS_INTERPRETATION_B_W,
+        _ => VipsInterpretation::VIPS_INTERPRETATION_sRGB,
+    }
+}
+
+/// Moves the pixels of an `ImageBuffer` into libvips without copying.
+/// Fails if the buffer has zero width or height, which libvips cannot represent.
+/// Float samples are scaled from 0..1 to 0..255, see `from_image_buffer`.
+impl<'a, P> TryFrom<ImageBuffer<P, Vec<P::Subpixel>>> for VipsImage<'a>
+    where P: Pixel + 'static, P::Subpixel: VipsPixel
+{
+    type Error = Box<Error>;
+
+    fn try_from(buffer: ImageBuffer<P, Vec<P::Subpixel>>) -> Result<VipsImage<'a>, Box<Error>> {
+        VipsImage::from_image_buffer(buffer)
+    }
+}
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── IMAGE CRATE ────────────────────────────────────────────────────────────────
+    //
+
+    /// Move the pixels of an `ImageBuffer` into libvips without copying; fails for an empty buffer.
+    ///
+    /// Float samples are in 0..1 in the image crate but 0..255 in libvips, so
+    /// float images are scaled by 255 as their pixels are computed.
+    pub fn from_image_buffer<P>(buffer: ImageBuffer<P, Vec<P::Subpixel>>) -> Result<VipsImage<'a>, Box<Error>>
+        where P: Pixel + 'static, P::Subpixel: VipsPixel
+    {
+        let (width, height) = buffer.dimensions();
+        let format = <P::Subpixel as VipsPixel>::FORMAT;
+        let image = VipsImage::from_vec(buffer.into_raw(), width, height, P::CHANNEL_COUNT, format)?;
+        unsafe {
+            (*image.c).Type = interpretation(format, P::CHANNEL_COUNT);
+        }
+        if is_float(format) {
+            scale(&image, FLOAT_SCALE)
+        } else {
+            Ok(image)
+        }
+    }
+
+    // variants without a libvips format are converted to 32 bit float RGBA first
+    pub fn from_dynamic_image(image: DynamicImage) -> Result<VipsImage<'a>, Box<Error>> {
+        match image {
+            DynamicImage::ImageLuma8(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageLumaA8(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageRgb8(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageRgba8(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageLuma16(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageLumaA16(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageRgb16(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageRgba16(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageRgb32F(buffer) => VipsImage::from_image_buffer(buffer),
+            DynamicImage::ImageRgba32F(buffer) => VipsImage::from_image_buffer(buffer),
+            other => VipsImage::from_image_buffer(other.into_rgba32f()),
+        }
+    }
+
+    // render the image into an `ImageBuffer`; fails if the format or band count differs from `P`.
+    // float images are scaled from 0..255 back to 0..1
+    pub fn to_image_buffer<P>(&self) -> Result<ImageBuffer<P, Vec<P::Subpixel>>, Box<Error>>
+        where P: Pixel + 'static, P::Subpixel: VipsPixel
+    {
+        if self.bands() != P::CHANNEL_COUNT as u32 {
+            return Err(format!("image has {} bands, the pixel type has {}", self.bands(), P::CHANNEL_COUNT).into());
+        }
+        let data = if is_float(self.format()) {
+            scale(self, 1.0 / FLOAT_SCALE)?.to_vec::<P::Subpixel>()?
+        } else {
+            self.to_vec::<P::Subpixel>()?
+        };
+        ImageBuffer::from_raw(self.width(), self.height(), data)
+            .ok_or_else(|| "image data is smaller than its size".into())
+    }
+
+    /// Render the image into the `DynamicImage` variant matching its format and bands.
+    ///
+    /// 8 and 16 bit images with 1 to 4 bands and float images with 3 or 4 bands
+    /// are supported. Cast other formats first.
+    pub fn to_dynamic_image(&self) -> Result<DynamicImage, Box<Error>> {
+        use ffi::VipsBandFormat::*;
+        let image = match (self.format(), self.bands()) {
+            (VIPS_FORMAT_UCHAR, 1) => DynamicImage::ImageLuma8(self.to_image_buffer()?),
+            (VIPS_FORMAT_UCHAR, 2) => DynamicImage::ImageLumaA8(self.to_image_buffer()?),
+            (VIPS_FORMAT_UCHAR, 3) => DynamicImage::ImageRgb8(self.to_image_buffer()?),
+            (VIPS_FORMAT_UCHAR, 4) => DynamicImage::ImageRgba8(self.to_image_buffer()?),
+            (VIPS_FORMAT_USHORT, 1) => DynamicImage::ImageLuma16(self.to_image_buffer()?),
+            (VIPS_FORMAT_USHORT, 2) => DynamicImage::ImageLumaA16(self.to_image_buffer()?),
+            (VIPS_FORMAT_USHORT, 3) => DynamicImage::ImageRgb16(self.to_image_buffer()?),
+            (VIPS_FORMAT_USHORT, 4) => DynamicImage::ImageRgba16(self.to_image_buffer()?),
+            (VIPS_FORMAT_FLOAT, 3) => DynamicImage::ImageRgb32F(self.to_image_buffer()?),
+            (VIPS_FORMAT_FLOAT, 4) => DynamicImage::ImageRgba32F(self.to_image_buffer()?),
+            (format, bands) => return Err(format!("no DynamicImage for {} band {:?}", bands, format).into()),
+        };
+        Ok(image)
+    }
+}
+
+/// Moves the pixels of a `DynamicImage` into libvips without copying.
+impl<'a> TryFrom<DynamicImage> for VipsImage<'a> {
+    type Error = Box<Error>;
+
+    fn try_from(image: DynamicImage) -> Result<VipsImage<'a>, Box<Error>> {
+        VipsImage::from_dynamic_image(image)
+    }
+}
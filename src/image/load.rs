@@ -0,0 +1,69 @@
+use ffi;
+use ffi::VipsAccess;
+use common::current_error;
+use operation::VipsOperation;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use super::VipsImage;
+
+/// Options for `VipsImage::from_file_with`. The defaults match `from_file`.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    // how the pixels will be read; sequential lets libvips stream large files
+    pub access: Option<VipsAccess>,
+    // load outside the operation cache, so a file changed on disk is read again
+    pub no_cache: bool,
+    // load through the cache, but replace any earlier load of the same file; needs libvips 8.13
+    #[cfg(feature = "vips-8-13")]
+    pub revalidate: bool,
+}
+
+// "page.tif[page=2]" -> ("page.tif", Some("[page=2]"))
+fn split_filename(path: &str) -> (&str, Option<&str>) {
+    match path.rfind('[') {
+        Some(open) if path.ends_with(']') => (&path[..open], Some(&path[open..])),
+        _ => (path, None),
+    }
+}
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── LOAD ───────────────────────────────────────────────────────────────────────
+    //
+
+    /// `from_file` with options. Loader options in `path`, such as
+    /// `"page.tif[page=2]"`, are applied as well.
+    pub fn from_file_with(path: &str, options: &LoadOptions) -> Result<VipsImage<'a>, Box<Error>> {
+        let (filename, option_string) = split_filename(path);
+        let loader = {
+            let path = CString::new(path)?;
+            let loader = unsafe { ffi::vips_foreign_find_load(path.as_ptr()) };
+            if loader.is_null() {
+                return Err(current_error().into());
+            }
+            unsafe { CStr::from_ptr(loader) }.to_string_lossy().into_owned()
+        };
+
+        let mut op = VipsOperation::new(&loader)?;
+        op.set_string("filename", filename)?;
+        if let Some(option_string) = option_string {
+            let option_string = CString::new(option_string)?;
+            if unsafe { ffi::vips_object_set_from_string(op.c as *mut ffi::VipsObject, option_string.as_ptr()) } != 0 {
+                return Err(current_error().into());
+            }
+        }
+        if let Some(access) = options.access {
+            op.set_int("access", access as i32)?;
+        }
+        #[cfg(feature = "vips-8-13")]
+        {
+            if options.revalidate {
+                op.set_revalidate(true)?;
+            }
+        }
+        op.set_no_cache(options.no_cache);
+        op.build()?;
+        op.get_image("out")
+    }
+}
@@ -0,0 +1,67 @@
+use ffi;
+use ffi::VipsBandFormat;
+use pixel::VipsPixel;
+use std::error::Error;
+use std::fmt;
+use std::mem::size_of;
+use std::os::raw::c_int;
+
+/// Why a pixel buffer was refused by `from_memory` or `from_memory_reference`.
+/// Recognise it with `err.downcast_ref::<MemoryLayoutError>()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MemoryLayoutError {
+    // width, height or bands is zero
+    Empty { width: u32, height: u32, bands: u8 },
+    // the dimensions do not fit libvips' int, or the byte size overflows
+    TooLarge { width: u32, height: u32, bands: u8 },
+    BufferTooSmall { len: usize, needed: usize },
+}
+
+impl fmt::Display for MemoryLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MemoryLayoutError::Empty { width, height, bands } =>
+                write!(f, "image size {}x{}x{} is empty", width, height, bands),
+            MemoryLayoutError::TooLarge { width, height, bands } =>
+                write!(f, "image size {}x{}x{} is too large", width, height, bands),
+            MemoryLayoutError::BufferTooSmall { len, needed } =>
+                write!(f, "buffer has {} bytes, the image needs {}", len, needed),
+        }
+    }
+}
+
+impl Error for MemoryLayoutError {
+    fn description(&self) -> &str {
+        "invalid pixel buffer layout"
+    }
+}
+
+// check `len` bytes can hold a width x height x bands image of `format`
+pub(crate) fn check_layout(len: usize, width: u32, height: u32, bands: u8, format: VipsBandFormat) -> Result<(), MemoryLayoutError> {
+    if width == 0 || height == 0 || bands == 0 {
+        return Err(MemoryLayoutError::Empty { width, height, bands });
+    }
+    let too_large = MemoryLayoutError::TooLarge { width, height, bands };
+    if width > c_int::MAX as u32 || height > c_int::MAX as u32 {
+        return Err(too_large);
+    }
+    let sizeof = unsafe { ffi::vips_format_sizeof(format) } as usize;
+    let needed = (width as usize).checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(bands as usize))
+        .and_then(|n| n.checked_mul(sizeof))
+        .ok_or(too_large)?;
+    if len < needed {
+        return Err(MemoryLayoutError::BufferTooSmall { len, needed });
+    }
+    Ok(())
+}
+
+// typed buffers of `len` elements must match the image size exactly, not just be large enough
+pub(crate) fn check_len<T: VipsPixel>(len: usize, width: u32, height: u32, bands: u8) -> Result<(), Box<Error>> {
+    check_layout(len * size_of::<T>(), width, height, bands, T::FORMAT)?;
+    let expected = width as usize * height as usize * bands as usize;
+    if len != expected {
+        return Err(format!("buffer has {} elements, {}x{}x{} needs {}", len, width, height, bands, expected).into());
+    }
+    Ok(())
+}
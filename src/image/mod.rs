@@ -8,15 +8,88 @@ use std::ffi::CString;
 use common::current_error;
 use std::ptr::null_mut;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::os::raw::c_int;
 use ::VipsInterpolate;
+use pixel::VipsPixel;
 
+mod pixels;
+pub use self::pixels::{PixelView, Rows, Tiles};
 
+mod progress;
+pub use self::progress::Progress;
+
+mod cancel;
+pub use self::cancel::{Cancelled, CancellationToken};
+
+mod dzsave;
+pub use self::dzsave::{DzLayout, DzOptions};
+
+mod header;
+
+mod load;
+pub use self::load::LoadOptions;
+
+mod diff;
+pub use self::diff::DiffReport;
+
+mod pages;
+pub use self::pages::Pages;
+
+mod typed;
+pub use self::typed::TypedImage;
+
+mod memory;
+pub use self::memory::MemoryLayoutError;
+
+#[cfg(feature = "image")]
+mod interop;
+
+#[cfg(feature = "ndarray")]
+mod array;
+
+/// An image, usually a lazy description of how to compute its pixels.
+///
+/// # Threads
+/// libvips images are immutable once built and all their read paths are
+/// threadsafe, so `VipsImage` is `Send + Sync`: it can be moved to or shared
+/// with worker threads, and operations can run on it from several threads at
+/// once. The `draw_*` methods are the only ones that modify pixels in place,
+/// and they take `&mut self` so they need exclusive ownership.
+///
+/// libvips keeps per-thread buffers; a thread that is not managed by libvips
+/// should call `vips::thread_shutdown()` before it exits.
 pub struct VipsImage<'a> {
     pub c: *mut ffi::VipsImage,
     marker: PhantomData<&'a()>,
 }
 
+unsafe impl<'a> Send for VipsImage<'a> {}
+unsafe impl<'a> Sync for VipsImage<'a> {}
+
+/// Cloning takes another reference to the same libvips image; no pixels are copied.
+///
+/// Use it to feed one source into several pipelines. libvips shares the
+/// upstream between them, and `copy_memory` decodes the source only once:
+///
+/// ```no_run
+/// # use vips::*;
+/// let source = VipsImage::from_file("kodim01.png").unwrap().copy_memory().unwrap();
+/// for &size in &[64, 256, 1024] {
+///     let img = source.clone();
+///     img.thumbnail(size, size, VipsSize::VIPS_SIZE_DOWN).unwrap()
+///         .write_to_file(format!("kodim01_{}.png", size)).unwrap();
+/// }
+/// ```
+impl<'a> Clone for VipsImage<'a> {
+    fn clone(&self) -> VipsImage<'a> {
+        unsafe {
+            ffi::g_object_ref(self.c as *mut c_void);
+        }
+        VipsImage { c: self.c, marker: PhantomData }
+    }
+}
+
 impl<'a> Drop for VipsImage<'a> {
     fn drop(&mut self) {
         unsafe {
@@ -26,11 +99,24 @@ impl<'a> Drop for VipsImage<'a> {
 }
 
 // callback used by gobjects
-pub unsafe extern "C" fn image_postclose(ptr: *mut ffi::VipsImage, user_data: *mut c_void) {
-    let b:Box<Box<[u8]>> = Box::from_raw(user_data as *mut Box<[u8]>);
+pub unsafe extern "C" fn image_postclose<T>(ptr: *mut ffi::VipsImage, user_data: *mut c_void) {
+    let b:Box<Box<[T]>> = Box::from_raw(user_data as *mut Box<[T]>);
     drop(b);
 }
 
+// hand `b` to the image, to be dropped once libvips has closed it
+unsafe fn free_on_close<T>(c: *mut ffi::VipsImage, b: Box<[T]>) {
+    let bb:Box<Box<_>> = Box::new(b);
+    let raw : *mut c_void = Box::into_raw(bb) as *mut c_void;
+
+    let callback: unsafe extern "C" fn() = ::std::mem::transmute(image_postclose::<T> as *const());
+    ffi::g_signal_connect_data(
+        c as *mut c_void, "postclose\0".as_ptr() as *const c_char,
+        Some(callback),
+        raw,
+        None, ffi::GConnectFlags::G_CONNECT_AFTER);
+}
+
 impl<'a> VipsImage<'a> {
 
     //
@@ -53,39 +139,48 @@ impl<'a> VipsImage<'a> {
         result(c)
     }
 
-    pub fn from_memory(buf: Vec<u8>, width: u32, height: u32, bands: u8, format: VipsBandFormat) -> Result<VipsImage<'a>, Box<Error>> {
+    // the band format comes from `T`, and `buf` must hold exactly width x height x bands pixels
+    pub fn from_memory<T: VipsPixel>(buf: Vec<T>, width: u32, height: u32, bands: u8) -> Result<VipsImage<'a>, Box<Error>> {
+        memory::check_len::<T>(buf.len(), width, height, bands)?;
+        VipsImage::from_vec(buf, width, height, bands, T::FORMAT)
+    }
+
+    // owned pixels of any scalar type, handed back to rust when the gobject is closed
+    pub(crate) fn from_vec<T: Copy>(buf: Vec<T>, width: u32, height: u32, bands: u8, format: VipsBandFormat) -> Result<VipsImage<'a>, Box<Error>> {
         let b:Box<[_]> = buf.into_boxed_slice();
+        let len = b.len() * ::std::mem::size_of::<T>();
+        memory::check_layout(len, width, height, bands, format)?;
         let c = unsafe {
             ffi::vips_image_new_from_memory(
                 b.as_ptr() as *const c_void,
-                b.len(),
+                len,
                 width as i32,
                 height as i32,
                 bands as i32,
                 format,
             )
         };
+        // the buffer is only handed over once libvips has accepted it
+        if c.is_null() {
+            return Err(current_error().into());
+        }
 
-        let bb:Box<Box<_>> = Box::new(b);
-        let raw : *mut c_void = Box::into_raw(bb) as *mut c_void;
-
-        unsafe {
-            let callback: unsafe extern "C" fn() = ::std::mem::transmute(image_postclose as *const());
-            ffi::g_signal_connect_data(
-                c as *mut c_void, "postclose\0".as_ptr() as *const c_char,
-                Some(callback),
-                raw,
-                None, ffi::GConnectFlags::G_CONNECT_AFTER);
-        };
-
+        unsafe { free_on_close(c, b) };
         result(c)
     }
 
     pub fn from_memory_reference(buf: &'a [u8], width: u32, height: u32, bands: u8, format: VipsBandFormat) -> Result<VipsImage, Box<Error>> {
+        VipsImage::from_slice(buf, width, height, bands, format)
+    }
+
+    // borrowed pixels of any scalar type
+    pub(crate) fn from_slice<T: Copy>(buf: &'a [T], width: u32, height: u32, bands: u8, format: VipsBandFormat) -> Result<VipsImage<'a>, Box<Error>> {
+        let len = buf.len() * ::std::mem::size_of::<T>();
+        memory::check_layout(len, width, height, bands, format)?;
         let c = unsafe {
             ffi::vips_image_new_from_memory(
                 buf.as_ptr() as *const c_void,
-                buf.len(),
+                len,
                 width as i32,
                 height as i32,
                 bands as i32,
@@ -105,11 +200,48 @@ impl<'a> VipsImage<'a> {
         result(c)
     }
 
+    // like from_buffer, but the image keeps the encoded bytes alive itself
+    pub(crate) fn from_owned_buffer(buf: Vec<u8>) -> Result<VipsImage<'a>, Box<Error>> {
+        let b:Box<[u8]> = buf.into_boxed_slice();
+        let c = unsafe {
+            ffi::vips_image_new_from_buffer(b.as_ptr() as *const c_void, b.len(), null(), null() as *const c_char)
+        };
+        if c.is_null() {
+            return Err(current_error().into());
+        }
+        unsafe { free_on_close(c, b) };
+        result(c)
+    }
+
     //
     // ─── DRAW ───────────────────────────────────────────────────────────────────────
     //
 
+    // GObject changes the count with atomic ops on other threads, so read it the same way.
+    // a count of 1 stays 1 while we hold &mut self, since only self can hand out a new ref
+    pub(crate) fn ref_count(&self) -> u32 {
+        unsafe {
+            let count = &(*(self.c as *mut ffi::GObject)).ref_count as *const ffi::guint as *const AtomicU32;
+            (*count).load(Ordering::SeqCst)
+        }
+    }
+
+    // draw ops write in place, so give self a private copy when the image is shared
+    // with clones or with downstream images
+    fn make_unique(&mut self) -> Result<(), Box<Error>> {
+        if self.ref_count() > 1 {
+            let copy = VipsImage::new_memory()?;
+            let ret = unsafe { ffi::vips_image_write(self.c, copy.c) };
+            if ret != 0 {
+                return Err(current_error().into());
+            }
+            *self = copy;
+        }
+        Ok(())
+    }
+
     pub fn draw_rect(&mut self, ink: &[f64], left:u32, top:u32, width:u32, height:u32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_rect(
                 self.c as *mut ffi::VipsImage,
@@ -123,6 +255,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_rect1(&mut self, ink: f64, left:u32, top:u32, width:u32, height:u32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_rect1(
                 self.c as *mut ffi::VipsImage,
@@ -136,6 +269,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_point(&mut self, ink: &[f64],x:i32,y:i32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_point(
                 self.c as *mut ffi::VipsImage,
@@ -148,6 +282,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_point1(&mut self, ink: f64,x:i32,y:i32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_point1(
                 self.c as *mut ffi::VipsImage,
@@ -159,6 +294,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_image(&mut self, img:&VipsImage,x:i32,y:i32,mode:VipsCombineMode) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_image(
                 self.c as *mut ffi::VipsImage,
@@ -172,6 +308,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_mask(&mut self, ink: &[f64], mask:&VipsImage,x:i32,y:i32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_mask(
                 self.c as *mut ffi::VipsImage,
@@ -185,6 +322,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_mask1(&mut self, ink: f64,mask:&VipsImage,x:i32,y:i32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_mask1(
                 self.c as *mut ffi::VipsImage,
@@ -197,6 +335,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_line(&mut self, ink: &[f64],x1:i32,y1:i32,x2:i32,y2:i32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_line(
                 self.c as *mut ffi::VipsImage,
@@ -211,6 +350,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_line1(&mut self, ink: f64,x1:i32,y1:i32,x2:i32,y2:i32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_line1(
                 self.c as *mut ffi::VipsImage,
@@ -224,6 +364,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_circle(&mut self, ink: &[f64],cx:i32,cy:i32,r:i32,fill:bool) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_circle(
                 self.c as *mut ffi::VipsImage,
@@ -239,6 +380,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_circle1(&mut self, ink: f64,cx:i32,cy:i32,r:i32, fill:bool) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_circle1(
                 self.c as *mut ffi::VipsImage,
@@ -253,6 +395,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_flood(&mut self, ink: &[f64],x:i32,y:i32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_flood(
                 self.c as *mut ffi::VipsImage,
@@ -265,6 +408,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_flood1(&mut self, ink: f64,x:i32,y:i32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_flood1(
                 self.c as *mut ffi::VipsImage,
@@ -276,6 +420,7 @@ impl<'a> VipsImage<'a> {
         result_draw(ret)
     }
     pub fn draw_smudge(&mut self, left:u32, top:u32, width:u32, height:u32) -> Result<(), Box<Error>> {
+        self.make_unique()?;
         let ret = unsafe {
             ffi::vips_draw_smudge(
                 self.c as *mut ffi::VipsImage,
@@ -410,13 +555,13 @@ impl<'a> VipsImage<'a> {
                     yr2,
                     xs2,
                     ys2,
-                    "search".as_ptr(),
+                    "search\0".as_ptr(),
                     search.unwrap_or(false) as i32,
-                    "hwindow".as_ptr(),
+                    "hwindow\0".as_ptr(),
                     hwindow.unwrap_or(1),
-                    "harea".as_ptr(),
+                    "harea\0".as_ptr(),
                     harea.unwrap_or(1),
-                    "interpolate".as_ptr(),
+                    "interpolate\0".as_ptr(),
                     interpolate.c as *mut ffi::VipsInterpolate,
                     null() as *const c_char),
                 None => ffi::vips_match(
@@ -431,11 +576,11 @@ impl<'a> VipsImage<'a> {
                     yr2,
                     xs2,
                     ys2,
-                    "search".as_ptr(),
+                    "search\0".as_ptr(),
                     search.unwrap_or(false) as i32,
-                    "hwindow".as_ptr(),
+                    "hwindow\0".as_ptr(),
                     hwindow.unwrap_or(1),
-                    "harea".as_ptr(),
+                    "harea\0".as_ptr(),
                     harea.unwrap_or(1),
                     null() as *const c_char)
             }
@@ -479,19 +624,58 @@ impl<'a> VipsImage<'a> {
     // ─── PROPERTIES ─────────────────────────────────────────────────────────────────
     //
 
-    fn width(&self) -> u32 {
+    pub fn width(&self) -> u32 {
         unsafe { (*self.c).Xsize as u32 }
     }
 
-    fn height(&self) -> u32 {
+    pub fn height(&self) -> u32 {
         unsafe { (*self.c).Ysize as u32 }
     }
 
+    pub fn bands(&self) -> u32 {
+        unsafe { (*self.c).Bands as u32 }
+    }
+
+    pub fn format(&self) -> VipsBandFormat {
+        unsafe { (*self.c).BandFmt }
+    }
+
+    //
+    // ─── CONVERSION ─────────────────────────────────────────────────────────────────
+    //
+
+    pub fn extract_area(&self, left: u32, top: u32, width: u32, height: u32) -> Result<VipsImage<'a>, Box<Error>> {
+        let mut out_ptr: *mut ffi::VipsImage = null_mut();
+        let ret = unsafe {
+            ffi::vips_extract_area(
+                self.c,
+                &mut out_ptr,
+                left as i32,
+                top as i32,
+                width as i32,
+                height as i32,
+                null() as *const c_char)
+        };
+        result_with_ret(out_ptr, ret)
+    }
+
+    // like extract_area, but cuts the same area from every page of an animation
+    pub fn crop(&self, left: u32, top: u32, width: u32, height: u32) -> Result<VipsImage<'a>, Box<Error>> {
+        if self.n_pages() > 1 {
+            return self.map_pages(|page| page.extract_area(left, top, width, height));
+        }
+        self.extract_area(left, top, width, height)
+    }
+
     //
     // ─── RESIZE ─────────────────────────────────────────────────────────────────────
     //
 
+    // animated images are thumbnailed frame by frame
     pub fn thumbnail(&self, width: u32, height: u32, size: VipsSize) -> Result<VipsImage<'a>, Box<Error>> {
+        if self.n_pages() > 1 {
+            return self.map_pages(|page| page.thumbnail(width, height, size));
+        }
         let mut out_ptr: *mut ffi::VipsImage = null_mut();
         unsafe {
             ffi::vips_thumbnail_image(self.c as *mut ffi::VipsImage, &mut out_ptr, width as i32, "height\0".as_ptr(), height as i32, "size\0".as_ptr(), size, null() as *const c_char);
@@ -500,7 +684,10 @@ impl<'a> VipsImage<'a> {
     }
 
     // default: block shrink + lanczos3
-    fn resize(&self, scale: f64, vscale: Option<f64>, kernel: Option<VipsKernel>) -> Result<VipsImage, Box<Error>> {
+    pub fn resize(&self, scale: f64, vscale: Option<f64>, kernel: Option<VipsKernel>) -> Result<VipsImage<'a>, Box<Error>> {
+        if self.n_pages() > 1 {
+            return self.map_pages(|page| page.resize(scale, vscale, kernel));
+        }
         let mut out_ptr: *mut ffi::VipsImage = null_mut();
         let ret = unsafe {
             ffi::vips_resize(
@@ -516,10 +703,10 @@ impl<'a> VipsImage<'a> {
         };
         result_with_ret(out_ptr, ret)
     }
-    fn resize_to_size(&self, width: u32, height: Option<u32>, kernel: Option<VipsKernel>) -> Result<VipsImage, Box<Error>> {
+    pub fn resize_to_size(&self, width: u32, height: Option<u32>, kernel: Option<VipsKernel>) -> Result<VipsImage<'a>, Box<Error>> {
         self.resize(
             width as f64 / self.width() as f64,
-            height.map(|h| h as f64 / self.height() as f64),
+            height.map(|h| h as f64 / self.page_height() as f64),
             kernel,
         )
     }
@@ -538,6 +725,38 @@ impl<'a> VipsImage<'a> {
         unimplemented!();
     }
 
+    // transform by the matrix [a b; c d]; default interpolator: bilinear
+    pub fn affine(&self, a: f64, b: f64, c: f64, d: f64, interpolate: Option<VipsInterpolate>) -> Result<VipsImage<'a>, Box<Error>> {
+        let mut out_ptr: *mut ffi::VipsImage = null_mut();
+        let ret = unsafe {
+            match interpolate {
+                Some(interpolate) => ffi::vips_affine(
+                    self.c,
+                    &mut out_ptr,
+                    a, b, c, d,
+                    "interpolate\0".as_ptr(),
+                    interpolate.c,
+                    null() as *const c_char),
+                None => ffi::vips_affine(
+                    self.c,
+                    &mut out_ptr,
+                    a, b, c, d,
+                    null() as *const c_char),
+            }
+        };
+        result_with_ret(out_ptr, ret)
+    }
+
+    //
+    // ─── MEMORY ─────────────────────────────────────────────────────────────────────
+    //
+
+    // render the image into memory once, so later pipelines read the pixels instead of recomputing them
+    pub fn copy_memory(&self) -> Result<VipsImage<'a>, Box<Error>> {
+        let c = unsafe { ffi::vips_image_copy_memory(self.c) };
+        result(c)
+    }
+
     //
     // ─── IO ─────────────────────────────────────────────────────────────────────────
     //
@@ -547,7 +766,7 @@ impl<'a> VipsImage<'a> {
         let ret = unsafe { ffi::vips_jpegsave(self.c as *mut ffi::VipsImage, path.as_ptr(), null() as *const c_char) };
         match ret {
             0 => Ok(()),
-            _ => Err(current_error().into()),
+            _ => Err(self.eval_error()),
         }
     }
 
@@ -556,27 +775,30 @@ impl<'a> VipsImage<'a> {
         let ret = unsafe { ffi::vips_image_write_to_file(self.c as *mut ffi::VipsImage, path.as_ptr(), null() as *const c_char) };
         match ret {
             0 => Ok(()),
-            _ => Err(current_error().into()),
+            _ => Err(self.eval_error()),
         }
     }
 
-    //
-    // ─── CONVERT ────────────────────────────────────────────────────────────────────
-    //
-
-    fn to_vec(&self) -> Vec<u8> {
+    // the suffix picks the format, with options as in write_to_file, e.g. ".png[compression=9]"
+    pub fn write_to_buffer(&self, suffix: &str) -> Result<Vec<u8>, Box<Error>> {
+        let suffix = CString::new(suffix)?;
+        let mut buf: *mut c_void = null_mut();
+        let mut size: usize = 0;
+        let ret = unsafe {
+            ffi::vips_image_write_to_buffer(self.c as *mut ffi::VipsImage, suffix.as_ptr(), &mut buf, &mut size, null() as *const c_char)
+        };
+        if ret != 0 {
+            return Err(self.eval_error());
+        }
         unsafe {
-            let mut result_size: usize = 0;
-            let memory: *mut u8 = ffi::vips_image_write_to_memory(self.c as *mut ffi::VipsImage, &mut result_size as *mut usize) as *mut u8;
-            let slice = ::std::slice::from_raw_parts_mut(memory, result_size);
-            let boxed_slice: Box<[u8]> = Box::from_raw(slice);
-            let vec = boxed_slice.into_vec();
-            vec
+            let data = ::std::slice::from_raw_parts(buf as *const u8, size).to_vec();
+            ffi::g_free(buf);
+            Ok(data)
         }
     }
 }
 
-fn result<'a>(ptr: *mut ffi::VipsImage) -> Result<VipsImage<'a>, Box<Error>> {
+pub(crate) fn result<'a>(ptr: *mut ffi::VipsImage) -> Result<VipsImage<'a>, Box<Error>> {
     if ptr.is_null() {
         Err(current_error().into())
     } else {
@@ -584,7 +806,7 @@ fn result<'a>(ptr: *mut ffi::VipsImage) -> Result<VipsImage<'a>, Box<Error>> {
     }
 }
 
-fn result_with_ret<'a>(ptr: *mut ffi::VipsImage, ret: c_int) -> Result<VipsImage<'a>, Box<Error>> {
+pub(crate) fn result_with_ret<'a>(ptr: *mut ffi::VipsImage, ret: c_int) -> Result<VipsImage<'a>, Box<Error>> {
     if ret == 0 {
         Ok(VipsImage { c: ptr, marker: PhantomData })
     } else {
@@ -0,0 +1,135 @@
+use ffi;
+use std::error::Error;
+use std::os::raw::{c_char, c_int};
+use std::ptr::{null, null_mut};
+use super::{VipsImage, result_with_ret};
+
+/// Iterates the frames of a multi-page image.
+pub struct Pages<'r, 'a: 'r> {
+    image: &'r VipsImage<'a>,
+    page: u32,
+    n_pages: u32,
+}
+
+impl<'r, 'a> Iterator for Pages<'r, 'a> {
+    type Item = Result<VipsImage<'a>, Box<Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.page >= self.n_pages {
+            return None;
+        }
+        let page_height = self.image.page_height();
+        let page = self.image.extract_area(0, self.page * page_height, self.image.width(), page_height);
+        self.page += 1;
+        Some(page)
+    }
+}
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── PAGES ──────────────────────────────────────────────────────────────────────
+    //
+
+    // multi-page images are loaded as a "toilet roll": all pages stacked vertically
+
+    /// The height of one page. The whole height unless `page-height` is set
+    /// and divides it exactly.
+    pub fn page_height(&self) -> u32 {
+        let height = self.height();
+        match self.get_int("page-height") {
+            Some(page_height) if page_height > 0 && page_height as u32 <= height && height.is_multiple_of(page_height as u32) => page_height as u32,
+            _ => height,
+        }
+    }
+
+    // number of pages stacked in this image
+    pub fn n_pages(&self) -> u32 {
+        self.height() / self.page_height()
+    }
+
+    // frame delays in milliseconds, one per page
+    pub fn delay(&self) -> Option<Vec<i32>> {
+        self.get_int_array("delay").or_else(|| {
+            // older libvips stores a single delay in centiseconds
+            self.get_int("gif-delay").map(|delay| vec![delay * 10; self.n_pages() as usize])
+        })
+    }
+
+    pub fn set_delay(&mut self, delay: &[i32]) -> Result<(), Box<Error>> {
+        self.set_int_array("delay", delay)?;
+        match delay.first() {
+            Some(&first) => self.set_int("gif-delay", first / 10),
+            None => Ok(()),
+        }
+    }
+
+    // number of times an animation plays; 0 means forever
+    pub fn loop_count(&self) -> Option<i32> {
+        self.get_int("loop").or_else(|| self.get_int("gif-loop"))
+    }
+
+    pub fn set_loop_count(&mut self, loop_count: i32) -> Result<(), Box<Error>> {
+        self.set_int("loop", loop_count)?;
+        self.set_int("gif-loop", loop_count)
+    }
+
+    pub fn pages<'r>(&'r self) -> Pages<'r, 'a> {
+        Pages {
+            image: self,
+            page: 0,
+            n_pages: self.n_pages(),
+        }
+    }
+
+    /// Stack frames of the same size into an animation.
+    ///
+    /// `delay` gives each frame's delay in milliseconds; pass an empty slice to
+    /// leave it unset.
+    pub fn from_pages(pages: &[VipsImage<'a>], delay: &[i32]) -> Result<VipsImage<'a>, Box<Error>> {
+        let first = match pages.first() {
+            Some(first) => first,
+            None => return Err("from_pages needs at least one page".into()),
+        };
+        if pages.iter().any(|page| page.width() != first.width() || page.height() != first.height()) {
+            return Err("all pages must have the same size".into());
+        }
+        if !delay.is_empty() && delay.len() != pages.len() {
+            return Err(format!("{} delays given for {} pages", delay.len(), pages.len()).into());
+        }
+
+        let mut array: Vec<*mut ffi::VipsImage> = pages.iter().map(|page| page.c).collect();
+        let mut out_ptr: *mut ffi::VipsImage = null_mut();
+        let ret = unsafe {
+            ffi::vips_arrayjoin(
+                array.as_mut_ptr(),
+                &mut out_ptr,
+                array.len() as c_int,
+                "across\0".as_ptr(),
+                1 as c_int,
+                null() as *const c_char)
+        };
+        let mut out = result_with_ret(out_ptr, ret)?;
+        out.set_int("page-height", first.height() as i32)?;
+        out.set_int("n-pages", pages.len() as i32)?;
+        if !delay.is_empty() {
+            out.set_delay(delay)?;
+        }
+        Ok(out)
+    }
+
+    // apply `f` to every page and stack the results again, keeping the animation metadata
+    pub(crate) fn map_pages<F>(&self, f: F) -> Result<VipsImage<'a>, Box<Error>>
+        where F: Fn(&VipsImage<'a>) -> Result<VipsImage<'a>, Box<Error>> {
+        let mut frames = Vec::with_capacity(self.n_pages() as usize);
+        for page in self.pages() {
+            frames.push(f(&page?)?);
+        }
+        let delay = self.delay().filter(|delay| delay.len() == frames.len()).unwrap_or_default();
+        let mut out = VipsImage::from_pages(&frames, &delay)?;
+        if let Some(loop_count) = self.loop_count() {
+            out.set_loop_count(loop_count)?;
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,203 @@
+use ffi;
+use ffi::VipsRect;
+use std::error::Error;
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::ptr::null;
+use std::os::raw::c_char;
+use std::mem::align_of;
+use std::slice;
+use common::current_error;
+use pixel::{VipsPixel, check_format};
+use region::VipsRegion;
+use super::VipsImage;
+
+/// A view of the pixels of an image that lives entirely in memory.
+pub struct PixelView<'r, T> {
+    // the memory image the pixels are read from, kept alive by the view
+    image: VipsImage<'r>,
+    width: usize,
+    height: usize,
+    bands: usize,
+    marker: PhantomData<T>,
+}
+
+impl<'r, T: VipsPixel> PixelView<'r, T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn bands(&self) -> usize {
+        self.bands
+    }
+
+    // all pixels, row-major, bands interleaved
+    pub fn as_slice(&self) -> &[T] {
+        // checked to be non-null and aligned for T in `pixels`
+        unsafe { slice::from_raw_parts((*self.image.c).data as *const T, self.width * self.height * self.bands) }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        let len = self.width * self.bands;
+        &self.as_slice()[y * len..(y + 1) * len]
+    }
+
+    // the bands of the pixel at (x, y)
+    pub fn pixel(&self, x: usize, y: usize) -> &[T] {
+        let start = (y * self.width + x) * self.bands;
+        &self.as_slice()[start..start + self.bands]
+    }
+}
+
+/// Iterates the lines of an image, computing one line at a time.
+pub struct Rows<'r, T> {
+    region: VipsRegion<'r>,
+    width: i32,
+    height: i32,
+    y: i32,
+    marker: PhantomData<T>,
+}
+
+impl<'r, T: VipsPixel> Iterator for Rows<'r, T> {
+    type Item = Result<Vec<T>, Box<Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.height {
+            return None;
+        }
+        let rect = VipsRect { left: 0, top: self.y, width: self.width, height: 1 };
+        self.y += 1;
+        Some(self.region.fetch(&rect))
+    }
+}
+
+/// Iterates an image in tiles, left to right then top to bottom.
+/// Tiles on the right and bottom edges are clipped to the image.
+pub struct Tiles<'r, T> {
+    region: VipsRegion<'r>,
+    width: i32,
+    height: i32,
+    tile_width: i32,
+    tile_height: i32,
+    x: i32,
+    y: i32,
+    marker: PhantomData<T>,
+}
+
+impl<'r, T: VipsPixel> Iterator for Tiles<'r, T> {
+    type Item = Result<(VipsRect, Vec<T>), Box<Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.height {
+            return None;
+        }
+        let rect = VipsRect {
+            left: self.x,
+            top: self.y,
+            width: self.tile_width.min(self.width - self.x),
+            height: self.tile_height.min(self.height - self.y),
+        };
+        // step by the clipped size, which never passes the edge of the image
+        self.x += rect.width;
+        if self.x >= self.width {
+            self.x = 0;
+            self.y += rect.height;
+        }
+        Some(self.region.fetch(&rect).map(|pixels| (rect, pixels)))
+    }
+}
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── PIXEL ACCESS ───────────────────────────────────────────────────────────────
+    //
+
+    // the value of every band at (x, y), converted to f64
+    pub fn getpoint(&self, x: u32, y: u32) -> Result<Vec<f64>, Box<Error>> {
+        let mut vector: *mut f64 = ::std::ptr::null_mut();
+        let mut n = 0;
+        let ret = unsafe {
+            ffi::vips_getpoint(self.c, &mut vector, &mut n, x as i32, y as i32, null() as *const c_char)
+        };
+        if ret != 0 {
+            return Err(current_error().into());
+        }
+        unsafe {
+            let values = slice::from_raw_parts(vector, n as usize).to_vec();
+            ffi::g_free(vector as *mut c_void);
+            Ok(values)
+        }
+    }
+
+    // render the whole image and copy it out
+    pub fn to_vec<T: VipsPixel>(&self) -> Result<Vec<T>, Box<Error>> {
+        check_format::<T>(self.format())?;
+        unsafe {
+            let mut size: usize = 0;
+            let memory = ffi::vips_image_write_to_memory(self.c, &mut size);
+            if memory.is_null() {
+                return Err(self.eval_error());
+            }
+            let vec = slice::from_raw_parts(memory as *const T, size / ::std::mem::size_of::<T>()).to_vec();
+            ffi::g_free(memory);
+            Ok(vec)
+        }
+    }
+
+    /// View the pixels of the image.
+    ///
+    /// Images created from memory are viewed in place; any other image is
+    /// rendered into a new memory image first (`copy_memory`), so the image
+    /// itself is never changed and can be shared with other threads meanwhile.
+    pub fn pixels<T: VipsPixel>(&self) -> Result<PixelView<'a, T>, Box<Error>> {
+        check_format::<T>(self.format())?;
+        let image = self.copy_memory()?;
+        let data = unsafe { (*image.c).data };
+        if data.is_null() {
+            return Err("image has no pixel data in memory".into());
+        }
+        if !(data as usize).is_multiple_of(align_of::<T>()) {
+            return Err(format!("pixel data is not aligned for {:?}", T::FORMAT).into());
+        }
+        Ok(PixelView {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bands: image.bands() as usize,
+            image,
+            marker: PhantomData,
+        })
+    }
+
+    // compute and return one line at a time
+    pub fn rows<'r, T: VipsPixel>(&'r self) -> Result<Rows<'r, T>, Box<Error>> {
+        Ok(Rows {
+            region: VipsRegion::new(self)?,
+            width: self.width() as i32,
+            height: self.height() as i32,
+            y: 0,
+            marker: PhantomData,
+        })
+    }
+
+    // compute and return one tile at a time; tiles larger than the image are clipped to it
+    pub fn tiles<'r, T: VipsPixel>(&'r self, tile_width: u32, tile_height: u32) -> Result<Tiles<'r, T>, Box<Error>> {
+        if tile_width == 0 || tile_height == 0 {
+            return Err("tile size must be positive".into());
+        }
+        Ok(Tiles {
+            region: VipsRegion::new(self)?,
+            width: self.width() as i32,
+            height: self.height() as i32,
+            tile_width: tile_width.min(self.width()) as i32,
+            tile_height: tile_height.min(self.height()) as i32,
+            x: 0,
+            y: 0,
+            marker: PhantomData,
+        })
+    }
+}
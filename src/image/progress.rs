@@ -0,0 +1,94 @@
+use ffi;
+use std::error::Error;
+use std::os::raw::{c_char, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::Duration;
+use super::VipsImage;
+
+/// How far the computation of an image has got.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    // 0 to 100
+    pub percent: u32,
+    // estimated time left
+    pub eta: Duration,
+    // total number of pixels to compute
+    pub tpels: u64,
+    // pixels computed so far
+    pub npels: u64,
+    // time since evaluation started
+    pub run: Duration,
+}
+
+impl<'a> From<&'a ffi::VipsProgress> for Progress {
+    fn from(p: &'a ffi::VipsProgress) -> Progress {
+        Progress {
+            percent: p.percent.max(0) as u32,
+            eta: Duration::from_secs(p.eta.max(0) as u64),
+            tpels: p.tpels.max(0) as u64,
+            npels: p.npels.max(0) as u64,
+            run: Duration::from_secs(p.run.max(0) as u64),
+        }
+    }
+}
+
+type ProgressCallback = Box<Fn(&Progress) + Send + Sync>;
+
+// the signals are emitted from libvips worker threads; a panic must not unwind into C
+unsafe extern "C" fn progress_trampoline(image: *mut ffi::VipsImage, progress: *mut ffi::VipsProgress, user_data: *mut c_void) {
+    let callback = &*(user_data as *const ProgressCallback);
+    let progress = Progress::from(&*progress);
+    let _ = catch_unwind(AssertUnwindSafe(|| callback(&progress)));
+}
+
+unsafe extern "C" fn progress_destroy(user_data: *mut c_void, closure: *mut ffi::GClosure) {
+    drop(Box::from_raw(user_data as *mut ProgressCallback));
+}
+
+impl<'a> VipsImage<'a> {
+
+    //
+    // ─── PROGRESS ───────────────────────────────────────────────────────────────────
+    //
+
+    /// Enable the preeval, eval and posteval signals on this image.
+    ///
+    /// Like the metadata setters, this gives self a lazy private copy first if
+    /// the image is shared with clones or the operation cache, so the handlers
+    /// only fire for evaluations of this image.
+    pub fn set_progress(&mut self, progress: bool) -> Result<(), Box<Error>> {
+        self.make_header_unique()?;
+        unsafe { ffi::vips_image_set_progress(self.c, progress as ffi::gboolean) };
+        Ok(())
+    }
+
+    // called once when computation of this image starts
+    pub fn on_preeval<F: Fn(&Progress) + Send + Sync + 'static>(&mut self, callback: F) -> Result<(), Box<Error>> {
+        self.connect_progress("preeval\0", Box::new(callback))
+    }
+
+    // called repeatedly while this image is computed
+    pub fn on_eval<F: Fn(&Progress) + Send + Sync + 'static>(&mut self, callback: F) -> Result<(), Box<Error>> {
+        self.connect_progress("eval\0", Box::new(callback))
+    }
+
+    // called once when computation of this image has finished
+    pub fn on_posteval<F: Fn(&Progress) + Send + Sync + 'static>(&mut self, callback: F) -> Result<(), Box<Error>> {
+        self.connect_progress("posteval\0", Box::new(callback))
+    }
+
+    // handlers can not be disconnected, so they go on an image only self holds
+    fn connect_progress(&mut self, signal: &'static str, callback: ProgressCallback) -> Result<(), Box<Error>> {
+        self.make_header_unique()?;
+        let raw = Box::into_raw(Box::new(callback)) as *mut c_void;
+        unsafe {
+            let handler: unsafe extern "C" fn() = ::std::mem::transmute(progress_trampoline as *const());
+            ffi::g_signal_connect_data(
+                self.c as *mut c_void, signal.as_ptr() as *const c_char,
+                Some(handler),
+                raw,
+                Some(progress_destroy), ffi::GConnectFlags::G_CONNECT_AFTER);
+        }
+        Ok(())
+    }
+}
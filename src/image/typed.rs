@@ -0,0 +1,94 @@
+use ffi;
+use pixel::{VipsPixel, check_format};
+use std::error::Error;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::os::raw::c_char;
+use std::ptr::{null, null_mut};
+use super::{VipsImage, result_with_ret};
+use super::memory::check_len;
+
+/// A `VipsImage` whose band format is known at compile time.
+///
+/// The format always matches `T`, so reading pixels back cannot fail on a
+/// type mismatch. Every untyped method is available through `Deref`.
+///
+/// ```no_run
+/// # use vips::*;
+/// let data: Vec<f32> = vec![0.5; 64 * 64];
+/// let img: TypedImage<f32> = TypedImage::from_memory(data, 64, 64, 1).unwrap();
+/// let bytes: TypedImage<u8> = img.cast().unwrap();
+/// ```
+pub struct TypedImage<'a, T: VipsPixel> {
+    image: VipsImage<'a>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: VipsPixel> Clone for TypedImage<'a, T> {
+    fn clone(&self) -> TypedImage<'a, T> {
+        TypedImage { image: self.image.clone(), marker: PhantomData }
+    }
+}
+
+impl<'a, T: VipsPixel> TypedImage<'a, T> {
+
+    //
+    // ─── CONSTRUCTORS ───────────────────────────────────────────────────────────────
+    //
+
+    // the buffer is handed to libvips and freed with the image
+    pub fn from_memory(buf: Vec<T>, width: u32, height: u32, bands: u8) -> Result<TypedImage<'a, T>, Box<Error>> {
+        let image = VipsImage::from_memory(buf, width, height, bands)?;
+        Ok(TypedImage { image, marker: PhantomData })
+    }
+
+    pub fn from_memory_reference(buf: &'a [T], width: u32, height: u32, bands: u8) -> Result<TypedImage<'a, T>, Box<Error>> {
+        check_len::<T>(buf.len(), width, height, bands)?;
+        let image = VipsImage::from_slice(buf, width, height, bands, T::FORMAT)?;
+        Ok(TypedImage { image, marker: PhantomData })
+    }
+
+    // fails if the band format of `image` is not `T`
+    pub fn from_image(image: VipsImage<'a>) -> Result<TypedImage<'a, T>, Box<Error>> {
+        check_format::<T>(image.format())?;
+        Ok(TypedImage { image, marker: PhantomData })
+    }
+
+    pub fn into_inner(self) -> VipsImage<'a> {
+        self.image
+    }
+
+    //
+    // ─── CONVERSION ─────────────────────────────────────────────────────────────────
+    //
+
+    // convert every band to `U`, clipping values that do not fit
+    pub fn cast<U: VipsPixel>(&self) -> Result<TypedImage<'a, U>, Box<Error>> {
+        let mut out_ptr: *mut ffi::VipsImage = null_mut();
+        let ret = unsafe {
+            ffi::vips_cast(self.image.c, &mut out_ptr, U::FORMAT, null() as *const c_char)
+        };
+        let image = result_with_ret(out_ptr, ret)?;
+        Ok(TypedImage { image, marker: PhantomData })
+    }
+
+    //
+    // ─── PIXEL ACCESS ───────────────────────────────────────────────────────────────
+    //
+
+    pub fn to_vec(&self) -> Result<Vec<T>, Box<Error>> {
+        self.image.to_vec::<T>()
+    }
+
+    pub fn getpoint(&self, x: u32, y: u32) -> Result<Vec<T>, Box<Error>> {
+        Ok(self.image.getpoint(x, y)?.into_iter().map(T::from_f64).collect())
+    }
+}
+
+impl<'a, T: VipsPixel> Deref for TypedImage<'a, T> {
+    type Target = VipsImage<'a>;
+
+    fn deref(&self) -> &VipsImage<'a> {
+        &self.image
+    }
+}
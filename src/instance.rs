@@ -1,14 +1,27 @@
 use std::sync::atomic::AtomicBool;
 use std::ffi::CString;
 use std::error::Error;
+use std::env;
 use std::os::raw::c_int;
 use std::sync::atomic::Ordering::Relaxed;
 use ::ffi;
+use cache;
 
 lazy_static! {
     static ref IS_INSTANCIATED: AtomicBool = AtomicBool::new(false);
 }
 
+// not in the generated bindings
+extern "C" {
+    fn vips_vector_set_enabled(enabled: ffi::gboolean);
+    fn vips_vector_isenabled() -> ffi::gboolean;
+}
+
+#[cfg(feature = "vips-8-13")]
+extern "C" {
+    fn vips_block_untrusted_set(state: ffi::gboolean);
+}
+
 pub struct VipsInstance { }
 
 impl VipsInstance {
@@ -27,6 +40,72 @@ impl VipsInstance {
             Ok(VipsInstance {})
         }
     }
+
+    /// Configure libvips before starting it.
+    ///
+    /// Settings left unset are read from the `VIPS_*` environment variables
+    /// listed on `VipsInstanceBuilder`, and otherwise keep the libvips defaults.
+    pub fn builder() -> VipsInstanceBuilder {
+        VipsInstanceBuilder::from_env()
+    }
+
+    //
+    // ─── RUNTIME SETTINGS ───────────────────────────────────────────────────────────
+    //
+
+    // number of worker threads per pipeline
+    pub fn concurrency(&self) -> u32 {
+        unsafe { ffi::vips_concurrency_get() as u32 }
+    }
+
+    pub fn set_concurrency(&self, concurrency: u32) {
+        unsafe { ffi::vips_concurrency_set(concurrency as c_int) }
+    }
+
+    // the cache settings are process-wide; these are the same as the `cache` functions
+    pub fn cache_max(&self) -> i32 {
+        cache::max()
+    }
+
+    pub fn set_cache_max(&self, max: i32) {
+        cache::set_max(max)
+    }
+
+    pub fn cache_max_mem(&self) -> usize {
+        cache::max_mem()
+    }
+
+    pub fn set_cache_max_mem(&self, max_mem: usize) {
+        cache::set_max_mem(max_mem)
+    }
+
+    pub fn cache_max_files(&self) -> i32 {
+        cache::max_files()
+    }
+
+    pub fn set_cache_max_files(&self, max_files: i32) {
+        cache::set_max_files(max_files)
+    }
+
+    // print cache hits and misses to stdout
+    pub fn set_cache_trace(&self, trace: bool) {
+        unsafe { ffi::vips_cache_set_trace(trace as ffi::gboolean) }
+    }
+
+    // SIMD code generation with liborc
+    pub fn vector_enabled(&self) -> bool {
+        unsafe { vips_vector_isenabled() != 0 }
+    }
+
+    pub fn set_vector_enabled(&self, enabled: bool) {
+        unsafe { vips_vector_set_enabled(enabled as ffi::gboolean) }
+    }
+
+    // refuse to run loaders that are not fuzzed against untrusted input
+    #[cfg(feature = "vips-8-13")]
+    pub fn set_block_untrusted(&self, block: bool) {
+        unsafe { vips_block_untrusted_set(block as ffi::gboolean) }
+    }
 }
 
 impl Drop for VipsInstance {
@@ -35,4 +114,147 @@ impl Drop for VipsInstance {
             ffi::vips_shutdown();
         }
     }
-}
\ No newline at end of file
+}
+
+/// Settings applied when the `VipsInstance` is created.
+///
+/// | Setting | Environment variable |
+/// |---------|----------------------|
+/// | `leak_test` | `VIPS_LEAK` |
+/// | `concurrency` | `VIPS_CONCURRENCY` |
+/// | `cache_max` | `VIPS_CACHE_MAX` |
+/// | `cache_max_mem` | `VIPS_CACHE_MAX_MEM` |
+/// | `cache_max_files` | `VIPS_CACHE_MAX_FILES` |
+/// | `cache_trace` | `VIPS_CACHE_TRACE` |
+/// | `vector_enabled` | `VIPS_NOVECTOR` (disables) |
+/// | `block_untrusted` | `VIPS_BLOCK_UNTRUSTED` (only with the `vips-8-13` feature) |
+#[derive(Debug, Clone)]
+pub struct VipsInstanceBuilder {
+    name: String,
+    leak_test: bool,
+    concurrency: Option<u32>,
+    cache_max: Option<i32>,
+    cache_max_mem: Option<usize>,
+    cache_max_files: Option<i32>,
+    cache_trace: Option<bool>,
+    vector_enabled: Option<bool>,
+    block_untrusted: Option<bool>,
+}
+
+fn env_var<T: ::std::str::FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().and_then(|v| v.trim().parse().ok())
+}
+
+// set and not "0" or "false"
+fn env_flag(key: &str) -> Option<bool> {
+    env::var(key).ok().map(|v| {
+        let v = v.trim();
+        !(v == "0" || v.eq_ignore_ascii_case("false"))
+    })
+}
+
+impl VipsInstanceBuilder {
+    fn from_env() -> VipsInstanceBuilder {
+        VipsInstanceBuilder {
+            name: "vips-rs".to_string(),
+            leak_test: env_flag("VIPS_LEAK").unwrap_or(false),
+            concurrency: env_var("VIPS_CONCURRENCY"),
+            cache_max: env_var("VIPS_CACHE_MAX"),
+            cache_max_mem: env_var("VIPS_CACHE_MAX_MEM"),
+            cache_max_files: env_var("VIPS_CACHE_MAX_FILES"),
+            cache_trace: env_flag("VIPS_CACHE_TRACE"),
+            vector_enabled: env_flag("VIPS_NOVECTOR").map(|novector| !novector),
+            // ignored without the feature, so a set variable does not make `build` fail
+            block_untrusted: if cfg!(feature = "vips-8-13") { env_flag("VIPS_BLOCK_UNTRUSTED") } else { None },
+        }
+    }
+
+    // program name passed to vips_init
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn leak_test(mut self, leak_test: bool) -> Self {
+        self.leak_test = leak_test;
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: u32) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    pub fn cache_max(mut self, max: i32) -> Self {
+        self.cache_max = Some(max);
+        self
+    }
+
+    pub fn cache_max_mem(mut self, max_mem: usize) -> Self {
+        self.cache_max_mem = Some(max_mem);
+        self
+    }
+
+    pub fn cache_max_files(mut self, max_files: i32) -> Self {
+        self.cache_max_files = Some(max_files);
+        self
+    }
+
+    pub fn cache_trace(mut self, trace: bool) -> Self {
+        self.cache_trace = Some(trace);
+        self
+    }
+
+    pub fn vector_enabled(mut self, enabled: bool) -> Self {
+        self.vector_enabled = Some(enabled);
+        self
+    }
+
+    // needs libvips 8.13; `build` fails if this is set without the `vips-8-13` feature
+    pub fn block_untrusted(mut self, block: bool) -> Self {
+        self.block_untrusted = Some(block);
+        self
+    }
+
+    pub fn build(self) -> Result<VipsInstance, Box<Error>> {
+        if cfg!(not(feature = "vips-8-13")) && self.block_untrusted == Some(true) {
+            return Err("block_untrusted needs the vips-8-13 feature".into());
+        }
+        let instance = VipsInstance::new(&self.name, self.leak_test)?;
+        if let Some(concurrency) = self.concurrency {
+            instance.set_concurrency(concurrency);
+        }
+        if let Some(max) = self.cache_max {
+            instance.set_cache_max(max);
+        }
+        if let Some(max_mem) = self.cache_max_mem {
+            instance.set_cache_max_mem(max_mem);
+        }
+        if let Some(max_files) = self.cache_max_files {
+            instance.set_cache_max_files(max_files);
+        }
+        if let Some(trace) = self.cache_trace {
+            instance.set_cache_trace(trace);
+        }
+        if let Some(enabled) = self.vector_enabled {
+            instance.set_vector_enabled(enabled);
+        }
+        #[cfg(feature = "vips-8-13")]
+        {
+            if let Some(block) = self.block_untrusted {
+                instance.set_block_untrusted(block);
+            }
+        }
+        Ok(instance)
+    }
+}
+
+/// Free the per-thread buffers libvips allocated for the calling thread.
+///
+/// Call it at the end of any thread you spawned that ran libvips operations.
+/// Threads created by libvips itself clean up on their own.
+pub fn thread_shutdown() {
+    unsafe {
+        ffi::vips_thread_shutdown();
+    }
+}
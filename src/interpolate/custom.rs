@@ -0,0 +1,176 @@
+// VipsInterpolate subclass implemented in rust
+
+use ffi;
+use region::VipsRegion;
+use common::{current_error, set_error};
+use std::error::Error;
+use std::ffi::CString;
+use std::mem::{size_of, ManuallyDrop};
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::{null, null_mut};
+use std::slice;
+use super::VipsInterpolate;
+
+// redeclared because the generated bindings take the flags as a single enum value
+extern "C" {
+    fn g_type_register_static(parent_type: ffi::GType, type_name: *const c_char, info: *const ffi::GTypeInfo, flags: c_uint) -> ffi::GType;
+}
+
+/// A resampling kernel usable wherever libvips takes an interpolator, such as
+/// `affine`, `mosaic1` and `match_`.
+///
+/// libvips calls `interpolate` from its worker threads, so implementations
+/// must be `Send + Sync`.
+pub trait CustomInterpolate: Send + Sync + 'static {
+    // width and height of the input window needed for one output pixel
+    fn window_size(&self) -> i32;
+
+    // distance from the top-left of the window to the pixel containing (x, y)
+    fn window_offset(&self) -> i32 {
+        ::std::cmp::max(0, self.window_size() / 2 - 1)
+    }
+
+    /// Write one output pixel to `out`.
+    ///
+    /// `out` holds one pixel of the input format, `bands * sizeof(format)` bytes.
+    /// `region` has been prepared to cover the window around (x, y), which are
+    /// in input image coordinates.
+    ///
+    /// libvips has no way to fail a single pixel, so on an error or a panic
+    /// the pixel is left black and the message is added to the libvips error
+    /// buffer, where `vips::current_error()` reads it.
+    fn interpolate(&self, out: &mut [u8], region: &VipsRegion, x: f64, y: f64) -> Result<(), Box<Error>>;
+}
+
+#[repr(C)]
+struct RustInterpolate {
+    parent_object: ffi::VipsInterpolate,
+    imp: *mut Box<CustomInterpolate>,
+}
+
+pub(super) const TYPE_NAME: &str = "VipsRustInterpolate";
+
+lazy_static! {
+    static ref INTERPOLATE_TYPE: ffi::GType = unsafe { register_type() };
+}
+
+unsafe fn imp<'i>(interpolate: *mut ffi::VipsInterpolate) -> Option<&'i CustomInterpolate> {
+    let imp = (*(interpolate as *mut RustInterpolate)).imp;
+    if imp.is_null() {
+        None
+    } else {
+        Some(&**imp)
+    }
+}
+
+unsafe extern "C" fn custom_interpolate(interpolate: *mut ffi::VipsInterpolate, out: *mut c_void, in_: *mut ffi::VipsRegion, x: f64, y: f64) {
+    let im = (*in_).im;
+    let len = (*im).Bands as usize * ffi::vips_format_sizeof((*im).BandFmt) as usize;
+    let out = slice::from_raw_parts_mut(out as *mut u8, len);
+    // the region belongs to libvips, so it is never unreffed here
+    let region = ManuallyDrop::new(VipsRegion::from_raw(in_));
+    let error = match imp(interpolate) {
+        Some(imp) => match catch_unwind(AssertUnwindSafe(|| imp.interpolate(out, &region, x, y))) {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "interpolator panicked".to_string(),
+        },
+        None => "interpolator has no implementation".to_string(),
+    };
+    // there is no way to fail from here, so record the error and leave a black pixel
+    set_error("interpolate", &error);
+    for byte in out.iter_mut() {
+        *byte = 0;
+    }
+}
+
+// a panic gives the smallest window, and is recorded like an interpolate error
+unsafe fn window<F: Fn(&CustomInterpolate) -> i32>(interpolate: *mut ffi::VipsInterpolate, default: i32, f: F) -> c_int {
+    match imp(interpolate) {
+        Some(imp) => catch_unwind(AssertUnwindSafe(|| f(imp))).unwrap_or_else(|_| {
+            set_error("interpolate", "interpolator panicked");
+            default
+        }),
+        None => default,
+    }
+}
+
+unsafe extern "C" fn custom_window_size(interpolate: *mut ffi::VipsInterpolate) -> c_int {
+    window(interpolate, 1, |imp| imp.window_size())
+}
+
+unsafe extern "C" fn custom_window_offset(interpolate: *mut ffi::VipsInterpolate) -> c_int {
+    window(interpolate, 0, |imp| imp.window_offset())
+}
+
+unsafe extern "C" fn custom_finalize(object: *mut ffi::GObject) {
+    let instance = object as *mut RustInterpolate;
+    if !(*instance).imp.is_null() {
+        drop(Box::from_raw((*instance).imp));
+        (*instance).imp = null_mut();
+    }
+    let class = (*(object as *mut ffi::GTypeInstance)).g_class;
+    let parent = ffi::g_type_class_peek_parent(class as *mut c_void) as *mut ffi::GObjectClass;
+    if let Some(finalize) = (*parent).finalize {
+        finalize(object);
+    }
+}
+
+unsafe extern "C" fn custom_class_init(g_class: *mut c_void, class_data: *mut c_void) {
+    let gobject_class = g_class as *mut ffi::GObjectClass;
+    (*gobject_class).finalize = Some(custom_finalize);
+
+    let object_class = g_class as *mut ffi::VipsObjectClass;
+    (*object_class).nickname = "rust\0".as_ptr() as *const c_char;
+    (*object_class).description = "interpolator implemented in rust\0".as_ptr() as *const c_char;
+
+    let interpolate_class = g_class as *mut ffi::VipsInterpolateClass;
+    (*interpolate_class).interpolate = Some(custom_interpolate);
+    (*interpolate_class).get_window_size = Some(custom_window_size);
+    (*interpolate_class).get_window_offset = Some(custom_window_offset);
+}
+
+unsafe fn register_type() -> ffi::GType {
+    let info = ffi::GTypeInfo {
+        class_size: size_of::<ffi::VipsInterpolateClass>() as u16,
+        base_init: None,
+        base_finalize: None,
+        class_init: Some(custom_class_init),
+        class_finalize: None,
+        class_data: null(),
+        instance_size: size_of::<RustInterpolate>() as u16,
+        n_preallocs: 0,
+        instance_init: None,
+        value_table: null(),
+    };
+    let name = CString::new(TYPE_NAME).unwrap_or_default();
+    g_type_register_static(ffi::vips_interpolate_get_type(), name.as_ptr(), &info, 0)
+}
+
+impl VipsInterpolate {
+
+    /// Wrap a `CustomInterpolate` in a libvips interpolator.
+    ///
+    /// Needs a live `VipsInstance`. `imp` is dropped together with the
+    /// interpolator, once every image using it has been freed.
+    pub fn custom<I: CustomInterpolate>(imp: I) -> Result<VipsInterpolate, Box<Error>> {
+        let gtype = *INTERPOLATE_TYPE;
+        if gtype == 0 {
+            return Err("could not register the rust interpolator type".into());
+        }
+        unsafe {
+            let c = ffi::g_object_new(gtype, null() as *const c_char) as *mut ffi::VipsInterpolate;
+            if c.is_null() {
+                return Err(current_error().into());
+            }
+            let boxed: Box<CustomInterpolate> = Box::new(imp);
+            (*(c as *mut RustInterpolate)).imp = Box::into_raw(Box::new(boxed));
+            let interpolate = VipsInterpolate { c, is_static: false };
+            if ffi::vips_object_build(c as *mut ffi::VipsObject) != 0 {
+                return Err(current_error().into());
+            }
+            Ok(interpolate)
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use region::VipsRegion;
+use pixel::{VipsPixel, check_format};
+use ffi;
+use ffi::VipsRect;
+use std::error::Error;
+use std::ffi::CString;
+use common::current_error;
+use introspect::{classes, class_nickname, class_description, class_type_name};
+use std::os::raw::c_void;
+
+mod custom;
+pub use self::custom::CustomInterpolate;
+
+
+/// An interpolator that can be created with `VipsInterpolate::new(nickname)`.
+#[derive(Debug, Clone)]
+pub struct InterpolatorInfo {
+    pub nickname: String,
+    pub description: String,
+    pub window_size: i32,
+}
+
+// interpolators hold no mutable state after construction
+pub struct VipsInterpolate {
+    pub c: *mut ffi::VipsInterpolate,
+    is_static: bool,
+}
+
+unsafe impl Send for VipsInterpolate {}
+unsafe impl Sync for VipsInterpolate {}
+
+impl<'a> Drop for VipsInterpolate {
+    fn drop(&mut self) {
+        if !self.is_static {
+            unsafe {
+                ffi::g_object_unref(self.c as *mut c_void);
+            }
+        }
+    }
+}
+
+
+impl VipsInterpolate {
+
+    //
+    // ─── STATIC ─────────────────────────────────────────────────────────────────────
+    //
+
+    // will not implement: vips_interpolate ()
+
+    // every interpolator libvips knows, sorted by nickname; needs a live `VipsInstance`
+    pub fn available() -> Vec<InterpolatorInfo> {
+        let classes = classes(unsafe { ffi::vips_interpolate_get_type() });
+        let mut available: Vec<InterpolatorInfo> = classes.into_iter()
+            // the rust wrapper cannot be made by nickname
+            .filter(|&class| unsafe { class_type_name(class) } != custom::TYPE_NAME)
+            .filter_map(|class| {
+                let nickname = unsafe { class_nickname(class) };
+                let window_size = VipsInterpolate::new(&nickname).ok()?.window_size();
+                Some(InterpolatorInfo {
+                    description: unsafe { class_description(class) },
+                    nickname,
+                    window_size,
+                })
+            })
+            .collect();
+        available.sort_by(|a, b| a.nickname.cmp(&b.nickname));
+        available
+    }
+
+    //
+    // ─── CONSTRUCTORS ───────────────────────────────────────────────────────────────
+    //
+
+    pub fn new(nickname: &str) -> Result<VipsInterpolate, Box<Error>> {
+        let nickname = CString::new(nickname)?;
+        let c = unsafe { ffi::vips_interpolate_new(nickname.as_ptr()) };
+        if c.is_null() {
+            Err(current_error().into())
+        } else {
+            Ok(VipsInterpolate { c, is_static:false })
+        }
+    }
+
+    pub fn nearest_static() -> VipsInterpolate {
+        let c = unsafe { ffi::vips_interpolate_nearest_static() };
+        VipsInterpolate {c, is_static:true}
+    }
+
+    pub fn bilinear_static() -> VipsInterpolate {
+        let c = unsafe { ffi::vips_interpolate_bilinear_static() };
+        VipsInterpolate {c, is_static:true}
+    }
+
+    //
+    // ─── PROPERTIES ─────────────────────────────────────────────────────────────────
+    //
+
+    pub fn method(&self) -> VipsInterpolateMethod {
+        let c = unsafe {
+            ffi::vips_interpolate_get_method(
+                self.c
+            )
+        };
+        VipsInterpolateMethod { c, interpolate: self }
+    }
+
+    pub fn window_size(&self) -> i32 {
+        unsafe {
+            ffi::vips_interpolate_get_window_size(
+                self.c
+            )
+        }
+    }
+
+    pub fn window_offset(&self) -> i32 {
+        unsafe {
+            ffi::vips_interpolate_get_window_offset(
+                self.c
+            )
+        }
+    }
+
+
+}
+
+/// The interpolation function of a `VipsInterpolate`, borrowed from it.
+pub struct VipsInterpolateMethod<'i> {
+    c: ffi::VipsInterpolateMethod,
+    interpolate: &'i VipsInterpolate,
+}
+
+impl<'i> VipsInterpolateMethod<'i> {
+    // the input pixels the interpolator reads to compute (x, y)
+    pub fn window(&self, x: f64, y: f64) -> VipsRect {
+        let size = self.interpolate.window_size();
+        let offset = self.interpolate.window_offset();
+        VipsRect {
+            left: x.floor() as i32 - offset,
+            top: y.floor() as i32 - offset,
+            width: size,
+            height: size,
+        }
+    }
+
+    /// Interpolate the pixel at (x, y) of `in_` into `out`.
+    ///
+    /// `in_` must hold pixels of type `T` and be prepared to cover `window(x, y)`,
+    /// and `out` must have room for one value per band.
+    pub fn call_into<T: VipsPixel>(&self, in_: &VipsRegion, out: &mut [T], x: f64, y: f64) -> Result<(), Box<Error>> {
+        check_format::<T>(in_.format())?;
+        let bands = in_.bands() as usize;
+        if out.len() < bands {
+            return Err(format!("output has room for {} values, the image has {} bands", out.len(), bands).into());
+        }
+        let window = self.window(x, y);
+        let valid = in_.valid();
+        let covered = unsafe { !(*in_.c).data.is_null() }
+            && window.left >= valid.left
+            && window.top >= valid.top
+            && window.left + window.width <= valid.left + valid.width
+            && window.top + window.height <= valid.top + valid.height;
+        if !covered {
+            return Err(format!("region {:?} does not cover the interpolation window {:?}", valid, window).into());
+        }
+        let method = self.c.ok_or("interpolator has no method")?;
+        unsafe {
+            method(self.interpolate.c, out.as_mut_ptr() as *mut c_void, in_.c, x, y);
+        }
+        Ok(())
+    }
+
+    // the interpolated value of every band at (x, y)
+    pub fn call<T: VipsPixel>(&self, in_: &VipsRegion, x: f64, y: f64) -> Result<Vec<T>, Box<Error>> {
+        let mut out = vec![T::from_f64(0.0); in_.bands() as usize];
+        self.call_into(in_, &mut out, x, y)?;
+        Ok(out)
+    }
+}
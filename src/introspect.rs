@@ -0,0 +1,225 @@
+//! Discover the operations libvips provides and the arguments they take.
+//!
+//! ```no_run
+//! # use vips::*;
+//! # let _instance = VipsInstance::new("introspect", false).unwrap();
+//! for op in vips::introspect::operations() {
+//!     let required: Vec<_> = op.arguments.iter().filter(|arg| arg.is_required()).map(|arg| &arg.name).collect();
+//!     println!("{}: {} {:?}", op.nickname, op.description, required);
+//! }
+//! ```
+
+use ffi;
+use ffi::{VipsArgumentFlags, VipsOperationFlags};
+use common::{G_TYPE_BOOLEAN, G_TYPE_INT, G_TYPE_UINT64, G_TYPE_ENUM, G_TYPE_FLAGS, G_TYPE_DOUBLE, G_TYPE_STRING};
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_uint, c_void};
+use std::ptr::null_mut;
+
+const OPERATION_FLAGS: [VipsOperationFlags; 4] = [
+    VipsOperationFlags::VIPS_OPERATION_SEQUENTIAL,
+    VipsOperationFlags::VIPS_OPERATION_SEQUENTIAL_UNBUFFERED,
+    VipsOperationFlags::VIPS_OPERATION_NOCACHE,
+    VipsOperationFlags::VIPS_OPERATION_DEPRECATED,
+];
+
+const ARGUMENT_FLAGS: [VipsArgumentFlags; 8] = [
+    VipsArgumentFlags::VIPS_ARGUMENT_REQUIRED,
+    VipsArgumentFlags::VIPS_ARGUMENT_CONSTRUCT,
+    VipsArgumentFlags::VIPS_ARGUMENT_SET_ONCE,
+    VipsArgumentFlags::VIPS_ARGUMENT_SET_ALWAYS,
+    VipsArgumentFlags::VIPS_ARGUMENT_INPUT,
+    VipsArgumentFlags::VIPS_ARGUMENT_OUTPUT,
+    VipsArgumentFlags::VIPS_ARGUMENT_DEPRECATED,
+    VipsArgumentFlags::VIPS_ARGUMENT_MODIFY,
+];
+
+/// An operation that can be created with `VipsOperation::new(nickname)`.
+#[derive(Debug, Clone)]
+pub struct OperationInfo {
+    pub nickname: String,
+    pub description: String,
+    pub flags: Vec<VipsOperationFlags>,
+    // in the order libvips lists them, required arguments first
+    pub arguments: Vec<ArgumentInfo>,
+}
+
+impl OperationInfo {
+    pub fn is_deprecated(&self) -> bool {
+        self.flags.contains(&VipsOperationFlags::VIPS_OPERATION_DEPRECATED)
+    }
+}
+
+/// One argument of an operation.
+#[derive(Debug, Clone)]
+pub struct ArgumentInfo {
+    pub name: String,
+    pub description: String,
+    // the GType name, e.g. "gdouble", "VipsImage" or "VipsKernel"
+    pub type_name: String,
+    pub flags: Vec<VipsArgumentFlags>,
+    pub default: Option<ArgumentDefault>,
+    // the range of numeric arguments
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl ArgumentInfo {
+    pub fn is_required(&self) -> bool {
+        self.flags.contains(&VipsArgumentFlags::VIPS_ARGUMENT_REQUIRED)
+    }
+
+    pub fn is_input(&self) -> bool {
+        self.flags.contains(&VipsArgumentFlags::VIPS_ARGUMENT_INPUT)
+    }
+
+    pub fn is_output(&self) -> bool {
+        self.flags.contains(&VipsArgumentFlags::VIPS_ARGUMENT_OUTPUT)
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.flags.contains(&VipsArgumentFlags::VIPS_ARGUMENT_DEPRECATED)
+    }
+}
+
+/// The default value of an optional argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentDefault {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    // the nickname of the enum value, as accepted on the vips command line
+    Enum(String),
+    String(String),
+}
+
+unsafe fn string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+unsafe extern "C" fn push_class(class: *mut ffi::VipsObjectClass, a: *mut c_void) -> *mut c_void {
+    (*(a as *mut Vec<*mut ffi::VipsObjectClass>)).push(class);
+    null_mut()
+}
+
+// every non-abstract class derived from `base`
+pub(crate) fn classes(base: ffi::GType) -> Vec<*mut ffi::VipsObjectClass> {
+    let mut classes: Vec<*mut ffi::VipsObjectClass> = Vec::new();
+    unsafe {
+        ffi::vips_class_map_all(base, Some(push_class), &mut classes as *mut _ as *mut c_void);
+    }
+    classes
+}
+
+pub(crate) unsafe fn class_nickname(class: *mut ffi::VipsObjectClass) -> String {
+    string((*class).nickname)
+}
+
+pub(crate) unsafe fn class_description(class: *mut ffi::VipsObjectClass) -> String {
+    string((*class).description)
+}
+
+pub(crate) unsafe fn class_type_name(class: *mut ffi::VipsObjectClass) -> String {
+    string(ffi::g_type_name((*(class as *mut ffi::GTypeClass)).g_type))
+}
+
+// the bindings declare flag fields as enums, which cannot hold OR'd values
+unsafe fn bits<T>(field: *const T) -> c_uint {
+    *(field as *const c_uint)
+}
+
+unsafe fn default_and_range(pspec: *mut ffi::GParamSpec) -> (Option<ArgumentDefault>, Option<f64>, Option<f64>) {
+    let value_type = (*pspec).value_type;
+    match ffi::g_type_fundamental(value_type) {
+        G_TYPE_BOOLEAN => {
+            let spec = pspec as *mut ffi::GParamSpecBoolean;
+            (Some(ArgumentDefault::Bool((*spec).default_value != 0)), None, None)
+        },
+        G_TYPE_INT => {
+            let spec = pspec as *mut ffi::GParamSpecInt;
+            (Some(ArgumentDefault::Int((*spec).default_value as i64)), Some((*spec).minimum as f64), Some((*spec).maximum as f64))
+        },
+        G_TYPE_UINT64 => {
+            let spec = pspec as *mut ffi::GParamSpecUInt64;
+            (Some(ArgumentDefault::Int((*spec).default_value as i64)), Some((*spec).minimum as f64), Some((*spec).maximum as f64))
+        },
+        G_TYPE_DOUBLE => {
+            let spec = pspec as *mut ffi::GParamSpecDouble;
+            (Some(ArgumentDefault::Double((*spec).default_value)), Some((*spec).minimum), Some((*spec).maximum))
+        },
+        G_TYPE_ENUM => {
+            let spec = pspec as *mut ffi::GParamSpecEnum;
+            let value = ffi::g_enum_get_value((*spec).enum_class, (*spec).default_value);
+            let default = if value.is_null() { None } else { Some(ArgumentDefault::Enum(string((*value).value_nick))) };
+            (default, None, None)
+        },
+        G_TYPE_FLAGS => {
+            let spec = pspec as *mut ffi::GParamSpecFlags;
+            (Some(ArgumentDefault::Int((*spec).default_value as i64)), None, None)
+        },
+        G_TYPE_STRING => {
+            let spec = pspec as *mut ffi::GParamSpecString;
+            let default = if (*spec).default_value.is_null() { None } else { Some(ArgumentDefault::String(string((*spec).default_value))) };
+            (default, None, None)
+        },
+        _ => (None, None, None),
+    }
+}
+
+unsafe extern "C" fn push_argument(object_class: *mut ffi::VipsObjectClass, pspec: *mut ffi::GParamSpec, argument_class: *mut ffi::VipsArgumentClass, a: *mut c_void, b: *mut c_void) -> *mut c_void {
+    // "nickname" and "description", which every VipsObject has
+    if (*pspec).owner_type == ffi::vips_object_get_type() {
+        return null_mut();
+    }
+    let flags = bits(&(*argument_class).flags);
+    let (default, min, max) = default_and_range(pspec);
+    (*(a as *mut Vec<ArgumentInfo>)).push(ArgumentInfo {
+        name: string((*pspec).name),
+        description: string(ffi::g_param_spec_get_blurb(pspec)),
+        type_name: string(ffi::g_type_name((*pspec).value_type)),
+        flags: ARGUMENT_FLAGS.iter().cloned().filter(|&flag| flags & flag as c_uint != 0).collect(),
+        default,
+        min,
+        max,
+    });
+    null_mut()
+}
+
+unsafe fn operation_info(class: *mut ffi::VipsObjectClass) -> OperationInfo {
+    let mut arguments: Vec<ArgumentInfo> = Vec::new();
+    ffi::vips_argument_class_map(class, Some(push_argument), &mut arguments as *mut _ as *mut c_void, null_mut());
+    let flags = bits(&(*(class as *mut ffi::VipsOperationClass)).flags);
+    OperationInfo {
+        nickname: class_nickname(class),
+        description: class_description(class),
+        flags: OPERATION_FLAGS.iter().cloned().filter(|&flag| flags & flag as c_uint != 0).collect(),
+        arguments,
+    }
+}
+
+/// Every operation libvips knows, including ones added with `register_operation`,
+/// sorted by nickname. Needs a live `VipsInstance`.
+pub fn operations() -> Vec<OperationInfo> {
+    let mut operations: Vec<OperationInfo> = unsafe {
+        classes(ffi::vips_operation_get_type()).into_iter().map(|class| operation_info(class)).collect()
+    };
+    operations.sort_by(|a, b| a.nickname.cmp(&b.nickname));
+    operations
+}
+
+// a single operation by nickname
+pub fn operation(nickname: &str) -> Result<OperationInfo, Box<Error>> {
+    let nickname = CString::new(nickname)?;
+    unsafe {
+        let class = ffi::vips_class_find("VipsOperation\0".as_ptr() as *const c_char, nickname.as_ptr());
+        if class.is_null() {
+            return Err(format!("no operation {}", nickname.to_string_lossy()).into());
+        }
+        Ok(operation_info(class as *mut ffi::VipsObjectClass))
+    }
+}
@@ -4,6 +4,16 @@
 extern crate vips_sys as ffi;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "image")]
+extern crate image as image_crate;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "json")]
+extern crate serde_json;
+#[cfg(feature = "async")]
+extern crate tokio;
 
 // re-exports modules
 mod common;
@@ -11,20 +21,60 @@ pub use common::*;
 
 mod instance;
 pub use instance::VipsInstance;
+pub use instance::VipsInstanceBuilder;
+pub use instance::thread_shutdown;
 
 mod image;
 pub use image::VipsImage;
+pub use image::{PixelView, Rows, Tiles};
+pub use image::Progress;
+pub use image::{Cancelled, CancellationToken};
+pub use image::{DzLayout, DzOptions};
+pub use image::Pages;
+pub use image::TypedImage;
+pub use image::MemoryLayoutError;
+pub use image::LoadOptions;
+pub use image::DiffReport;
 
 mod interpolate;
 pub use interpolate::VipsInterpolate;
 pub use interpolate::VipsInterpolateMethod;
+pub use interpolate::CustomInterpolate;
+pub use interpolate::InterpolatorInfo;
 
 mod region;
 pub use region::VipsRegion;
+pub use region::RegionMut;
+
+mod generate;
+pub use generate::{Generator, Header};
+
+mod operation;
+pub use operation::VipsOperation;
+pub use operation::{register_operation, OperationSpec, ArgumentSpec, ArgumentKind, Arguments};
+
+mod pixel;
+pub use pixel::VipsPixel;
 
 mod buffer;
 pub use buffer::VipsBuffer;
 
+pub mod cache;
+pub mod introspect;
+
+pub mod pipeline;
+pub use pipeline::Pipeline;
+
+pub mod recipe;
+
+#[cfg(feature = "async")]
+mod async_vips;
+#[cfg(feature = "async")]
+pub use async_vips::{AsyncVips, AsyncError, VipsFuture};
+
+mod stats;
+pub use stats::{stats, Stats};
+
 // re-exports simple structs
 pub use ffi::VipsRect;
 
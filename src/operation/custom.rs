@@ -0,0 +1,287 @@
+// VipsOperation subclasses implemented in rust
+
+use ffi;
+use ffi::VipsArgumentFlags;
+use image::{VipsImage, result};
+use common::set_error;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::mem::size_of;
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::marker::PhantomData;
+use std::ptr::null;
+
+// redeclared because the generated bindings take the flags as a single enum value
+extern "C" {
+    fn vips_object_class_install_argument(cls: *mut ffi::VipsObjectClass, pspec: *mut ffi::GParamSpec, flags: c_uint, priority: c_int, offset: c_uint);
+    fn g_type_register_static(parent_type: ffi::GType, type_name: *const c_char, info: *const ffi::GTypeInfo, flags: c_uint) -> ffi::GType;
+}
+
+/// The type and range of an argument of a custom operation.
+#[derive(Debug, Copy, Clone)]
+pub enum ArgumentKind {
+    Image,
+    Int { min: i32, max: i32, default: i32 },
+    Double { min: f64, max: f64, default: f64 },
+    Bool { default: bool },
+    String,
+}
+
+#[derive(Debug)]
+pub struct ArgumentSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: ArgumentKind,
+    pub flags: &'static [VipsArgumentFlags],
+}
+
+/// Describes a `VipsOperation` subclass. Usually written with `vips_operation!`.
+pub struct OperationSpec {
+    pub nickname: &'static str,
+    pub description: &'static str,
+    pub arguments: &'static [ArgumentSpec],
+    pub build: fn(&mut Arguments) -> Result<(), Box<Error>>,
+}
+
+/// Define a static `OperationSpec`.
+///
+/// ```ignore
+/// vips_operation!(INVERT, "rs_invert", "invert an 8-bit image", invert, [
+///     ("in", "Input image", Image, [VIPS_ARGUMENT_REQUIRED, VIPS_ARGUMENT_INPUT]),
+///     ("out", "Output image", Image, [VIPS_ARGUMENT_REQUIRED, VIPS_ARGUMENT_OUTPUT]),
+///     ("gain", "Gain", Double { min: 0.0, max: 10.0, default: 1.0 }, [VIPS_ARGUMENT_INPUT]),
+/// ]);
+///
+/// register_operation(&INVERT).unwrap();
+/// ```
+#[macro_export]
+macro_rules! vips_operation {
+    ($name:ident, $nickname:expr, $description:expr, $build:expr, [
+        $( ($arg:expr, $arg_description:expr, $kind:ident $({ $($field:tt)* })*, [ $($flag:ident),* ]) ),* $(,)*
+    ]) => {
+        static $name: $crate::OperationSpec = $crate::OperationSpec {
+            nickname: $nickname,
+            description: $description,
+            arguments: &[ $(
+                $crate::ArgumentSpec {
+                    name: $arg,
+                    description: $arg_description,
+                    kind: $crate::ArgumentKind::$kind $({ $($field)* })*,
+                    flags: &[ $( $crate::VipsArgumentFlags::$flag ),* ],
+                }
+            ),* ],
+            build: $build,
+        };
+    };
+}
+
+#[repr(C)]
+struct RustOperationClass {
+    parent_class: ffi::VipsOperationClass,
+    spec: *const OperationSpec,
+}
+
+// every argument is stored in an 8 byte slot after the parent instance
+const SLOT: usize = 8;
+
+fn slot_offset(index: usize) -> usize {
+    size_of::<ffi::VipsOperation>().div_ceil(SLOT) * SLOT + index * SLOT
+}
+
+// pspecs and class strings live as long as the type, which is forever
+fn leak(s: &str) -> *const c_char {
+    CString::new(s).unwrap_or_default().into_raw()
+}
+
+unsafe extern "C" fn custom_class_init(g_class: *mut c_void, class_data: *mut c_void) {
+    let class = g_class as *mut RustOperationClass;
+    let spec = &*(class_data as *const OperationSpec);
+    (*class).spec = spec;
+
+    let gobject_class = g_class as *mut ffi::GObjectClass;
+    (*gobject_class).set_property = Some(ffi::vips_object_set_property);
+    (*gobject_class).get_property = Some(ffi::vips_object_get_property);
+
+    let object_class = g_class as *mut ffi::VipsObjectClass;
+    (*object_class).nickname = leak(spec.nickname);
+    (*object_class).description = leak(spec.description);
+    (*object_class).build = Some(custom_build);
+
+    for (i, arg) in spec.arguments.iter().enumerate() {
+        let name = leak(arg.name);
+        let blurb = leak(arg.description);
+        let flags = ffi::GParamFlags::G_PARAM_READWRITE;
+        let pspec = match arg.kind {
+            ArgumentKind::Image => ffi::g_param_spec_object(name, name, blurb, ffi::vips_image_get_type(), flags),
+            ArgumentKind::Int { min, max, default } => ffi::g_param_spec_int(name, name, blurb, min, max, default, flags),
+            ArgumentKind::Double { min, max, default } => ffi::g_param_spec_double(name, name, blurb, min, max, default, flags),
+            ArgumentKind::Bool { default } => ffi::g_param_spec_boolean(name, name, blurb, default as ffi::gboolean, flags),
+            ArgumentKind::String => ffi::g_param_spec_string(name, name, blurb, null(), flags),
+        };
+        let arg_flags = arg.flags.iter().fold(0, |acc, &flag| acc | flag as c_uint);
+        ffi::g_object_class_install_property(gobject_class, (i + 1) as c_uint, pspec);
+        vips_object_class_install_argument(object_class, pspec, arg_flags, i as c_int, slot_offset(i) as c_uint);
+    }
+}
+
+// libvips does not apply pspec defaults to struct members, so do it here
+unsafe extern "C" fn custom_instance_init(instance: *mut ffi::GTypeInstance, g_class: *mut c_void) {
+    let spec = &*(*(g_class as *mut RustOperationClass)).spec;
+    for (i, arg) in spec.arguments.iter().enumerate() {
+        let slot = (instance as *mut u8).add(slot_offset(i));
+        match arg.kind {
+            ArgumentKind::Int { default, .. } => *(slot as *mut c_int) = default,
+            ArgumentKind::Double { default, .. } => *(slot as *mut f64) = default,
+            ArgumentKind::Bool { default } => *(slot as *mut ffi::gboolean) = default as ffi::gboolean,
+            ArgumentKind::Image | ArgumentKind::String => {},
+        }
+    }
+}
+
+unsafe extern "C" fn custom_build(object: *mut ffi::VipsObject) -> c_int {
+    let class = (*(object as *mut ffi::GTypeInstance)).g_class as *mut RustOperationClass;
+    let parent = ffi::g_type_class_peek_parent(class as *mut c_void) as *mut ffi::VipsObjectClass;
+    if let Some(build) = (*parent).build {
+        if build(object) != 0 {
+            return -1;
+        }
+    }
+
+    let spec = &*(*class).spec;
+    let mut args = Arguments { object, spec, marker: PhantomData };
+    match catch_unwind(AssertUnwindSafe(|| (spec.build)(&mut args))) {
+        Ok(Ok(())) => 0,
+        Ok(Err(e)) => {
+            set_error(spec.nickname, &e.to_string());
+            -1
+        },
+        Err(_) => {
+            set_error(spec.nickname, "operation panicked");
+            -1
+        },
+    }
+}
+
+/// Register `spec` as a subclass of `VipsOperation`.
+///
+/// After this the operation can be created by nickname with `VipsOperation::new`,
+/// run with `vips::call`, and is listed alongside the built-in operations.
+/// Needs a live `VipsInstance`.
+pub fn register_operation(spec: &'static OperationSpec) -> Result<(), Box<Error>> {
+    let type_name = CString::new(format!("VipsRust_{}", spec.nickname))?;
+    unsafe {
+        if ffi::g_type_from_name(type_name.as_ptr()) != 0 {
+            return Err(format!("operation {} is already registered", spec.nickname).into());
+        }
+        let info = ffi::GTypeInfo {
+            class_size: size_of::<RustOperationClass>() as u16,
+            base_init: None,
+            base_finalize: None,
+            class_init: Some(custom_class_init),
+            class_finalize: None,
+            class_data: spec as *const OperationSpec as *const c_void,
+            instance_size: slot_offset(spec.arguments.len()) as u16,
+            n_preallocs: 0,
+            instance_init: Some(custom_instance_init),
+            value_table: null(),
+        };
+        let gtype = g_type_register_static(ffi::vips_operation_get_type(), type_name.as_ptr(), &info, 0);
+        if gtype == 0 {
+            return Err(format!("could not register operation {}", spec.nickname).into());
+        }
+    }
+    Ok(())
+}
+
+/// The arguments of a custom operation while it is being built.
+pub struct Arguments<'o> {
+    object: *mut ffi::VipsObject,
+    spec: &'static OperationSpec,
+    marker: PhantomData<&'o ()>,
+}
+
+impl<'o> Arguments<'o> {
+    // the slot of an argument, checked to be of the kind the caller reads or writes
+    fn slot(&self, name: &str, kind: fn(&ArgumentKind) -> bool, expected: &str) -> Result<*mut u8, Box<Error>> {
+        match self.spec.arguments.iter().position(|arg| arg.name == name) {
+            Some(i) if kind(&self.spec.arguments[i].kind) => Ok(unsafe { (self.object as *mut u8).add(slot_offset(i)) }),
+            Some(_) => Err(format!("argument {} of {} is not {}", name, self.spec.nickname, expected).into()),
+            None => Err(format!("{} has no argument {}", self.spec.nickname, name).into()),
+        }
+    }
+
+    // the image is only borrowed from the operation, so it can not outlive the build
+    pub fn image<'s>(&'s self, name: &str) -> Result<VipsImage<'s>, Box<Error>> {
+        let ptr = unsafe { *(self.slot(name, is_image, "an image")? as *const *mut ffi::VipsImage) };
+        if ptr.is_null() {
+            return Err(format!("argument {} is not set", name).into());
+        }
+        unsafe { ffi::g_object_ref(ptr as *mut c_void) };
+        result(ptr)
+    }
+
+    pub fn int(&self, name: &str) -> Result<i32, Box<Error>> {
+        Ok(unsafe { *(self.slot(name, is_int, "an int")? as *const c_int) })
+    }
+
+    pub fn double(&self, name: &str) -> Result<f64, Box<Error>> {
+        Ok(unsafe { *(self.slot(name, is_double, "a double")? as *const f64) })
+    }
+
+    pub fn bool(&self, name: &str) -> Result<bool, Box<Error>> {
+        Ok(unsafe { *(self.slot(name, is_bool, "a bool")? as *const ffi::gboolean) != 0 })
+    }
+
+    pub fn string(&self, name: &str) -> Result<Option<String>, Box<Error>> {
+        let ptr = unsafe { *(self.slot(name, is_string, "a string")? as *const *const c_char) };
+        if ptr.is_null() {
+            Ok(None)
+        } else {
+            Ok(Some(unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()))
+        }
+    }
+
+    // whether `name` is declared with VIPS_ARGUMENT_OUTPUT
+    fn is_output(&self, name: &str) -> bool {
+        self.spec.arguments.iter()
+            .find(|arg| arg.name == name)
+            .is_some_and(|arg| arg.flags.contains(&VipsArgumentFlags::VIPS_ARGUMENT_OUTPUT))
+    }
+
+    // outputs are set through the property system. libvips only refs input
+    // images, an output keeps the reference it is given, so hand it one of its own.
+    // the setters take &self so outputs can be made from images borrowed with `image`
+    pub fn set_image(&self, name: &str, image: &VipsImage) -> Result<(), Box<Error>> {
+        self.slot(name, is_image, "an image")?;
+        let output = self.is_output(name);
+        let name = CString::new(name)?;
+        unsafe {
+            if output {
+                ffi::g_object_ref(image.c as *mut c_void);
+            }
+            ffi::g_object_set(self.object as *mut c_void, name.as_ptr(), image.c, null() as *const c_char);
+        }
+        Ok(())
+    }
+
+    pub fn set_int(&self, name: &str, value: i32) -> Result<(), Box<Error>> {
+        self.slot(name, is_int, "an int")?;
+        let name = CString::new(name)?;
+        unsafe { ffi::g_object_set(self.object as *mut c_void, name.as_ptr(), value as c_int, null() as *const c_char) };
+        Ok(())
+    }
+
+    pub fn set_double(&self, name: &str, value: f64) -> Result<(), Box<Error>> {
+        self.slot(name, is_double, "a double")?;
+        let name = CString::new(name)?;
+        unsafe { ffi::g_object_set(self.object as *mut c_void, name.as_ptr(), value, null() as *const c_char) };
+        Ok(())
+    }
+}
+
+fn is_image(kind: &ArgumentKind) -> bool { matches!(*kind, ArgumentKind::Image) }
+fn is_int(kind: &ArgumentKind) -> bool { matches!(*kind, ArgumentKind::Int { .. }) }
+fn is_double(kind: &ArgumentKind) -> bool { matches!(*kind, ArgumentKind::Double { .. }) }
+fn is_bool(kind: &ArgumentKind) -> bool { matches!(*kind, ArgumentKind::Bool { .. }) }
+fn is_string(kind: &ArgumentKind) -> bool { matches!(*kind, ArgumentKind::String) }
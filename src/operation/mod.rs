@@ -0,0 +1,250 @@
+// generic access to any libvips operation by nickname
+
+use ffi;
+use image::{VipsImage, result};
+use common::{current_error, G_TYPE_BOOLEAN, G_TYPE_INT, G_TYPE_ENUM, G_TYPE_FLAGS, G_TYPE_DOUBLE, G_TYPE_STRING};
+use std::error::Error;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr::{null, null_mut};
+
+mod custom;
+pub use self::custom::{register_operation, OperationSpec, ArgumentSpec, ArgumentKind, Arguments};
+
+// what a typed setter or getter passes through the varargs
+#[derive(Copy, Clone)]
+enum ValueKind {
+    Any,
+    Int,
+    Double,
+    Bool,
+    String,
+    Image,
+    Blob,
+}
+
+impl ValueKind {
+    fn accepts(self, value_type: ffi::GType) -> bool {
+        unsafe {
+            match self {
+                ValueKind::Any => true,
+                // enums and flags are an int in the varargs too
+                ValueKind::Int => value_type == G_TYPE_INT || matches!(ffi::g_type_fundamental(value_type), G_TYPE_ENUM | G_TYPE_FLAGS),
+                ValueKind::Double => value_type == G_TYPE_DOUBLE,
+                ValueKind::Bool => value_type == G_TYPE_BOOLEAN,
+                ValueKind::String => value_type == G_TYPE_STRING,
+                ValueKind::Image => ffi::g_type_is_a(value_type, ffi::vips_image_get_type()) != 0,
+                ValueKind::Blob => ffi::g_type_is_a(value_type, ffi::vips_blob_get_type()) != 0,
+            }
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ValueKind::Any => "a value",
+            ValueKind::Int => "an int or enum",
+            ValueKind::Double => "a double",
+            ValueKind::Bool => "a bool",
+            ValueKind::String => "a string",
+            ValueKind::Image => "an image",
+            ValueKind::Blob => "a blob",
+        }
+    }
+}
+
+/// A single call to a libvips operation, looked up by nickname.
+///
+/// Set the arguments, `build` it, then read the outputs. Building goes through
+/// the libvips operation cache, so an identical call reuses the earlier result,
+/// unless `set_no_cache` or `set_revalidate` is used.
+///
+/// ```no_run
+/// # use vips::*;
+/// # let img: VipsImage = VipsImage::from_file("kodim01.png").unwrap();
+/// let mut op = VipsOperation::new("gaussblur").unwrap();
+/// op.set_image("in", &img).unwrap();
+/// op.set_double("sigma", 2.0).unwrap();
+/// op.build().unwrap();
+/// let blurred = op.get_image("out").unwrap();
+/// ```
+pub struct VipsOperation<'a> {
+    pub c: *mut ffi::VipsOperation,
+    no_cache: bool,
+    marker: PhantomData<&'a ()>,
+}
+
+// setting arguments mutates the operation, so it can move between threads but not be shared
+unsafe impl<'a> Send for VipsOperation<'a> {}
+
+impl<'a> Drop for VipsOperation<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::vips_object_unref_outputs(self.c as *mut ffi::VipsObject);
+            ffi::g_object_unref(self.c as *mut c_void);
+        }
+    }
+}
+
+impl<'a> VipsOperation<'a> {
+
+    //
+    // ─── CONSTRUCTORS ───────────────────────────────────────────────────────────────
+    //
+
+    pub fn new(nickname: &str) -> Result<VipsOperation<'a>, Box<Error>> {
+        let nickname = CString::new(nickname)?;
+        let c = unsafe { ffi::vips_operation_new(nickname.as_ptr()) };
+        if c.is_null() {
+            Err(current_error().into())
+        } else {
+            Ok(VipsOperation { c, no_cache: false, marker: PhantomData })
+        }
+    }
+
+    //
+    // ─── ARGUMENTS ──────────────────────────────────────────────────────────────────
+    //
+
+    // the C name of an argument, checked to exist on this operation and to
+    // hold `kind`, since g_object_set/get read their varargs by the pspec type
+    fn argument(&self, name: &str, kind: ValueKind) -> Result<CString, Box<Error>> {
+        let name = CString::new(name)?;
+        let mut pspec = null_mut();
+        let mut argument_class = null_mut();
+        let mut argument_instance = null_mut();
+        let ret = unsafe {
+            ffi::vips_object_get_argument(
+                self.c as *mut ffi::VipsObject,
+                name.as_ptr(),
+                &mut pspec,
+                &mut argument_class,
+                &mut argument_instance)
+        };
+        if ret != 0 {
+            return Err(current_error().into());
+        }
+        let value_type = unsafe { (*pspec).value_type };
+        if kind.accepts(value_type) {
+            Ok(name)
+        } else {
+            Err(format!("argument {} is not {}", name.to_string_lossy(), kind.name()).into())
+        }
+    }
+
+    pub fn set_image(&mut self, name: &str, image: &VipsImage<'a>) -> Result<(), Box<Error>> {
+        let name = self.argument(name, ValueKind::Image)?;
+        unsafe { ffi::g_object_set(self.c as *mut c_void, name.as_ptr(), image.c, null() as *const c_char) };
+        Ok(())
+    }
+
+    // also used for enums, which libvips passes as int
+    pub fn set_int(&mut self, name: &str, value: i32) -> Result<(), Box<Error>> {
+        let name = self.argument(name, ValueKind::Int)?;
+        unsafe { ffi::g_object_set(self.c as *mut c_void, name.as_ptr(), value as c_int, null() as *const c_char) };
+        Ok(())
+    }
+
+    pub fn set_double(&mut self, name: &str, value: f64) -> Result<(), Box<Error>> {
+        let name = self.argument(name, ValueKind::Double)?;
+        unsafe { ffi::g_object_set(self.c as *mut c_void, name.as_ptr(), value, null() as *const c_char) };
+        Ok(())
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) -> Result<(), Box<Error>> {
+        let name = self.argument(name, ValueKind::Bool)?;
+        unsafe { ffi::g_object_set(self.c as *mut c_void, name.as_ptr(), value as ffi::gboolean, null() as *const c_char) };
+        Ok(())
+    }
+
+    pub fn set_string(&mut self, name: &str, value: &str) -> Result<(), Box<Error>> {
+        let name = self.argument(name, ValueKind::String)?;
+        let value = CString::new(value)?;
+        unsafe { ffi::g_object_set(self.c as *mut c_void, name.as_ptr(), value.as_ptr(), null() as *const c_char) };
+        Ok(())
+    }
+
+    // parse `value` the way the vips command line does, e.g. enum nicknames or "1 2 3" for arrays
+    pub fn set_from_string(&mut self, name: &str, value: &str) -> Result<(), Box<Error>> {
+        let name = self.argument(name, ValueKind::Any)?;
+        let value = CString::new(value)?;
+        let ret = unsafe { ffi::vips_object_set_argument_from_string(self.c as *mut ffi::VipsObject, name.as_ptr(), value.as_ptr()) };
+        match ret {
+            0 => Ok(()),
+            _ => Err(current_error().into()),
+        }
+    }
+
+    //
+    // ─── BUILD ──────────────────────────────────────────────────────────────────────
+    //
+
+    // skip the cache: neither reuse an earlier identical call nor keep this one
+    pub fn set_no_cache(&mut self, no_cache: bool) {
+        self.no_cache = no_cache;
+    }
+
+    /// Build through the cache, but replace an earlier identical call instead
+    /// of reusing it, e.g. to see a file that changed on disk. Needs libvips 8.13.
+    ///
+    /// Only loaders take a `revalidate` argument; other operations depend on
+    /// nothing but their inputs and return an error here.
+    #[cfg(feature = "vips-8-13")]
+    pub fn set_revalidate(&mut self, revalidate: bool) -> Result<(), Box<Error>> {
+        self.set_bool("revalidate", revalidate)
+    }
+
+    pub fn build(&mut self) -> Result<(), Box<Error>> {
+        let ret = unsafe {
+            if self.no_cache {
+                ffi::vips_object_build(self.c as *mut ffi::VipsObject)
+            } else {
+                ffi::vips_cache_operation_buildp(&mut self.c)
+            }
+        };
+        match ret {
+            0 => Ok(()),
+            _ => Err(current_error().into()),
+        }
+    }
+
+    //
+    // ─── OUTPUTS ────────────────────────────────────────────────────────────────────
+    //
+
+    pub fn get_image(&self, name: &str) -> Result<VipsImage<'a>, Box<Error>> {
+        let name = self.argument(name, ValueKind::Image)?;
+        let mut out: *mut ffi::VipsImage = null_mut();
+        unsafe { ffi::g_object_get(self.c as *mut c_void, name.as_ptr(), &mut out, null() as *const c_char) };
+        result(out)
+    }
+
+    pub fn get_int(&self, name: &str) -> Result<i32, Box<Error>> {
+        let name = self.argument(name, ValueKind::Int)?;
+        let mut out: c_int = 0;
+        unsafe { ffi::g_object_get(self.c as *mut c_void, name.as_ptr(), &mut out, null() as *const c_char) };
+        Ok(out)
+    }
+
+    // a copy of a blob output, such as the buffer written by a `_buffer` saver
+    pub fn get_blob(&self, name: &str) -> Result<Vec<u8>, Box<Error>> {
+        let name = self.argument(name, ValueKind::Blob)?;
+        let mut area: *mut ffi::VipsArea = null_mut();
+        unsafe { ffi::g_object_get(self.c as *mut c_void, name.as_ptr(), &mut area, null() as *const c_char) };
+        if area.is_null() {
+            return Err(current_error().into());
+        }
+        unsafe {
+            let data = ::std::slice::from_raw_parts((*area).data as *const u8, (*area).length).to_vec();
+            ffi::vips_area_unref(area);
+            Ok(data)
+        }
+    }
+
+    pub fn get_double(&self, name: &str) -> Result<f64, Box<Error>> {
+        let name = self.argument(name, ValueKind::Double)?;
+        let mut out: f64 = 0.0;
+        unsafe { ffi::g_object_get(self.c as *mut c_void, name.as_ptr(), &mut out, null() as *const c_char) };
+        Ok(out)
+    }
+}
@@ -0,0 +1,236 @@
+//! Chains of operations recorded up front and run later.
+//!
+//! A `Pipeline` is only a description: nothing is loaded or computed until
+//! `run` or `run_file` is called, and the same pipeline can be run on any
+//! number of inputs, from several threads at once.
+//!
+//! ```no_run
+//! # use vips::*;
+//! use vips::pipeline::{Pipeline, Step};
+//!
+//! let pipeline = Pipeline::new()
+//!     .load_options("[access=sequential]")
+//!     .then(Step::new("autorot"))
+//!     .then(Step::new("thumbnail_image").arg("width", 300).arg("crop", "attention"))
+//!     .then(Step::new("sharpen"))
+//!     .save_options("[Q=80,strip]");
+//!
+//! let _instance = VipsInstance::new("pipeline", false).unwrap();
+//! pipeline.validate().unwrap();
+//! for name in &["a", "b", "c"] {
+//!     pipeline.run_file(format!("{}.jpg", name), format!("{}_small.jpg", name)).unwrap();
+//! }
+//! ```
+
+use image::VipsImage;
+use introspect::{self, OperationInfo};
+use operation::VipsOperation;
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// The value of an operation argument.
+///
+/// Values are handed to libvips as strings, parsed the same way as on the
+/// vips command line, so enums are given by nickname (`"attention"`) and
+/// arrays as space separated numbers (`"1 2 3"`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    String(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Double(value) => write!(f, "{}", value),
+            Value::String(ref value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Value { Value::Bool(value) }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Value { Value::Int(value as i64) }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Value { Value::Int(value) }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Value { Value::Double(value) }
+}
+
+impl<'s> From<&'s str> for Value {
+    fn from(value: &'s str) -> Value { Value::String(value.to_string()) }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Value { Value::String(value) }
+}
+
+/// One operation of a pipeline, with every argument except its image input and output.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Step {
+    pub nickname: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub arguments: Vec<(String, Value)>,
+}
+
+impl Step {
+    pub fn new(nickname: &str) -> Step {
+        Step { nickname: nickname.to_string(), arguments: Vec::new() }
+    }
+
+    pub fn arg<V: Into<Value>>(mut self, name: &str, value: V) -> Step {
+        self.arguments.push((name.to_string(), value.into()));
+        self
+    }
+
+    // the image argument the previous step's output is passed to, and the one read back
+    fn image_arguments(info: &OperationInfo) -> Result<(String, String), String> {
+        let image_argument = |output: bool| info.arguments.iter()
+            .find(|arg| arg.is_required() && arg.is_output() == output && arg.type_name == "VipsImage")
+            .map(|arg| arg.name.clone());
+        match (image_argument(false), image_argument(true)) {
+            (Some(input), Some(output)) => Ok((input, output)),
+            _ => Err("does not take an image and make an image".to_string()),
+        }
+    }
+
+    // an operation with every argument set, ready to be given its input
+    fn operation<'a>(&self) -> Result<(VipsOperation<'a>, String, String), String> {
+        let info = introspect::operation(&self.nickname).map_err(|e| e.to_string())?;
+        let (input, output) = Step::image_arguments(&info)?;
+        let mut op = VipsOperation::new(&self.nickname).map_err(|e| e.to_string())?;
+        for &(ref name, ref value) in &self.arguments {
+            if !info.arguments.iter().any(|arg| &arg.name == name && arg.is_input()) || name == &input {
+                return Err(format!("has no argument {}", name));
+            }
+            op.set_from_string(name, &value.to_string()).map_err(|e| format!("{}: {}", name, e.to_string().trim()))?;
+        }
+        let missing: Vec<&str> = info.arguments.iter()
+            .filter(|arg| arg.is_required() && arg.is_input() && arg.name != input)
+            .filter(|arg| !self.arguments.iter().any(|&(ref name, _)| name == &arg.name))
+            .map(|arg| arg.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("needs {}", missing.join(", ")));
+        }
+        Ok((op, input, output))
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.nickname)?;
+        for &(ref name, ref value) in &self.arguments {
+            write!(f, " {}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A recorded chain of operations. See the module documentation.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Pipeline {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub load_options: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub steps: Vec<Step>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub save_options: Option<String>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    // appended to the input filename by `run_file`, e.g. "[access=sequential]"
+    pub fn load_options(mut self, options: &str) -> Pipeline {
+        self.load_options = Some(options.to_string());
+        self
+    }
+
+    pub fn then(mut self, step: Step) -> Pipeline {
+        self.steps.push(step);
+        self
+    }
+
+    // appended to the output filename by `run_file`, e.g. "[Q=80,strip]"
+    pub fn save_options(mut self, options: &str) -> Pipeline {
+        self.save_options = Some(options.to_string());
+        self
+    }
+
+    /// Check that every step names an operation that takes and makes an image,
+    /// that its arguments exist and parse, and that no required argument is missing.
+    ///
+    /// Operations are created and their arguments set, but nothing is built,
+    /// so no pixels are computed. Needs a live `VipsInstance`.
+    pub fn validate(&self) -> Result<(), Box<Error>> {
+        for (i, step) in self.steps.iter().enumerate() {
+            step.operation().map_err(|e| format!("step {} ({}): {}", i + 1, step.nickname, e))?;
+        }
+        Ok(())
+    }
+
+    /// Apply every step to `input`.
+    ///
+    /// The result is lazy like any other image; its pixels are computed when
+    /// it is saved or read.
+    pub fn run<'a>(&self, input: &VipsImage<'a>) -> Result<VipsImage<'a>, Box<Error>> {
+        let mut image = input.clone();
+        for (i, step) in self.steps.iter().enumerate() {
+            let fail = |e: String| format!("step {} ({}): {}", i + 1, step.nickname, e);
+            let (mut op, input, output) = step.operation().map_err(&fail)?;
+            op.set_image(&input, &image)?;
+            op.build().map_err(|e| fail(e.to_string()))?;
+            image = op.get_image(&output)?;
+        }
+        Ok(image)
+    }
+
+    // load `input`, run the steps and save to `output`; the file suffix picks the format
+    pub fn run_file<S: AsRef<str>, T: AsRef<str>>(&self, input: S, output: T) -> Result<(), Box<Error>> {
+        let input = format!("{}{}", input.as_ref(), self.load_options.as_ref().map_or("", |s| s.as_str()));
+        let output = format!("{}{}", output.as_ref(), self.save_options.as_ref().map_or("", |s| s.as_str()));
+        let image = VipsImage::from_file(input)?;
+        self.run(&image)?.write_to_file(output)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, Box<Error>> {
+        Ok(::serde_json::to_string_pretty(self)?)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Pipeline, Box<Error>> {
+        Ok(::serde_json::from_str(json)?)
+    }
+}
+
+/// One line per step, between the load and save options.
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "load{}", self.load_options.as_ref().map_or(String::new(), |s| format!(" {}", s)))?;
+        for step in &self.steps {
+            writeln!(f, "{}", step)?;
+        }
+        write!(f, "save{}", self.save_options.as_ref().map_or(String::new(), |s| format!(" {}", s)))
+    }
+}
@@ -0,0 +1,29 @@
+use ffi::VipsBandFormat;
+
+/// A Rust scalar type that has a matching `VipsBandFormat`.
+///
+/// It is used to reinterpret pixel memory owned by libvips as `&[T]`,
+/// so it is only implemented for the plain numeric types libvips uses.
+pub unsafe trait VipsPixel: Copy + 'static {
+    const FORMAT: VipsBandFormat;
+
+    // libvips reports values such as getpoint results as f64
+    fn from_f64(value: f64) -> Self;
+}
+
+unsafe impl VipsPixel for u8 { const FORMAT: VipsBandFormat = VipsBandFormat::VIPS_FORMAT_UCHAR; fn from_f64(value: f64) -> Self { value as u8 } }
+unsafe impl VipsPixel for i8 { const FORMAT: VipsBandFormat = VipsBandFormat::VIPS_FORMAT_CHAR; fn from_f64(value: f64) -> Self { value as i8 } }
+unsafe impl VipsPixel for u16 { const FORMAT: VipsBandFormat = VipsBandFormat::VIPS_FORMAT_USHORT; fn from_f64(value: f64) -> Self { value as u16 } }
+unsafe impl VipsPixel for i16 { const FORMAT: VipsBandFormat = VipsBandFormat::VIPS_FORMAT_SHORT; fn from_f64(value: f64) -> Self { value as i16 } }
+unsafe impl VipsPixel for u32 { const FORMAT: VipsBandFormat = VipsBandFormat::VIPS_FORMAT_UINT; fn from_f64(value: f64) -> Self { value as u32 } }
+unsafe impl VipsPixel for i32 { const FORMAT: VipsBandFormat = VipsBandFormat::VIPS_FORMAT_INT; fn from_f64(value: f64) -> Self { value as i32 } }
+unsafe impl VipsPixel for f32 { const FORMAT: VipsBandFormat = VipsBandFormat::VIPS_FORMAT_FLOAT; fn from_f64(value: f64) -> Self { value as f32 } }
+unsafe impl VipsPixel for f64 { const FORMAT: VipsBandFormat = VipsBandFormat::VIPS_FORMAT_DOUBLE; fn from_f64(value: f64) -> Self { value } }
+
+pub(crate) fn check_format<T: VipsPixel>(format: VipsBandFormat) -> Result<(), String> {
+    if T::FORMAT == format {
+        Ok(())
+    } else {
+        Err(format!("pixel type mismatch: image is {:?}, requested {:?}", format, T::FORMAT))
+    }
+}
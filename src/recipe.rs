@@ -0,0 +1,248 @@
+//! Recipes: pipelines written in the syntax of the vips command line.
+//!
+//! Each line is one operation with the image input and output left out.
+//! Required arguments are positional, in the order `vips <operation>` lists
+//! them, and optional ones are `--name value`. Boolean options take no value
+//! (`--linear`) unless written as `--linear=false`. `load` and `save` lines
+//! give the options appended to the input and output filenames. A `#` outside
+//! a quoted string starts a comment, and values are kept as written unless
+//! they are plain numbers or booleans for arguments of that type.
+//!
+//! ```text
+//! # make a 300x200 preview
+//! load [access=sequential]
+//! autorot
+//! thumbnail 300 --height 200 --crop attention
+//! sharpen --sigma 0.5
+//! save [Q=80,strip]
+//! ```
+//!
+//! `thumbnail` is read as `thumbnail_image`, since in a recipe its input is
+//! the image made by the previous line rather than a file.
+
+use introspect::{self, ArgumentInfo, OperationInfo};
+use operation::VipsOperation;
+use pipeline::{Pipeline, Step, Value};
+use std::error::Error;
+use std::fmt;
+
+// operations whose command line name loads a file, and their image equivalent
+const ALIASES: [(&str, &str); 1] = [
+    ("thumbnail", "thumbnail_image"),
+];
+
+/// A recipe that could not be parsed, with the position of the offending token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeError {
+    // 1-based
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {} (at `{}`)", self.line, self.column, self.message, self.token)
+    }
+}
+
+impl Error for RecipeError {
+    fn description(&self) -> &str {
+        "invalid recipe"
+    }
+}
+
+struct Token {
+    column: usize,
+    text: String,
+}
+
+// split on whitespace, keeping "quoted strings" together
+fn tokenize(line: &str) -> Result<Vec<Token>, (usize, String)> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let column = line[..start].chars().count() + 1;
+        let mut text = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, c)) => text.push(c),
+                    None => return Err((column, "unterminated quote".to_string())),
+                }
+            }
+        } else {
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(Token { column, text });
+    }
+    Ok(tokens)
+}
+
+// the text before any # that is not inside a quoted string
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {},
+        }
+    }
+    line
+}
+
+// numbers and booleans given to arguments of that type keep it, so recipes
+// serialize the same as built pipelines. anything that would not print back as
+// the same text, e.g. "007" or "1e3", stays a string for libvips to parse
+fn value(text: &str, arg: &ArgumentInfo) -> Value {
+    let typed = match arg.type_name.as_str() {
+        "gint" | "guint" | "gint64" | "guint64" => text.parse().ok().map(Value::Int),
+        "gdouble" => text.parse().ok().map(Value::Double),
+        "gboolean" => text.parse().ok().map(Value::Bool),
+        _ => None,
+    };
+    typed.filter(|value| value.to_string() == text).unwrap_or_else(|| Value::String(text.to_string()))
+}
+
+struct LineParser<'t> {
+    line: usize,
+    tokens: &'t [Token],
+}
+
+impl<'t> LineParser<'t> {
+    fn error(&self, token: &Token, message: String) -> RecipeError {
+        RecipeError { line: self.line, column: token.column, token: token.text.clone(), message }
+    }
+
+    fn options(&self, keyword: &str) -> Result<Option<String>, RecipeError> {
+        match self.tokens.len() {
+            1 => Ok(None),
+            2 if self.tokens[1].text.starts_with('[') && self.tokens[1].text.ends_with(']') => Ok(Some(self.tokens[1].text.clone())),
+            2 => Err(self.error(&self.tokens[1], format!("{} options must look like [name=value,...]", keyword))),
+            _ => Err(self.error(&self.tokens[2], format!("{} takes a single [options] token", keyword))),
+        }
+    }
+
+    fn step(&self) -> Result<Step, RecipeError> {
+        let name = &self.tokens[0];
+        let nickname = ALIASES.iter().find(|&&(alias, _)| alias == name.text).map_or(name.text.as_str(), |&(_, op)| op);
+        let info: OperationInfo = introspect::operation(nickname)
+            .map_err(|_| self.error(name, format!("unknown operation {}", name.text)))?;
+        let is_image = |arg: &ArgumentInfo, output: bool| arg.is_required() && arg.is_output() == output && arg.type_name == "VipsImage";
+        let image_input = match (info.arguments.iter().find(|arg| is_image(arg, false)), info.arguments.iter().find(|arg| is_image(arg, true))) {
+            (Some(input), Some(_)) => input.name.clone(),
+            _ => return Err(self.error(name, format!("{} does not take an image and make an image", nickname))),
+        };
+        let positional: Vec<&ArgumentInfo> = info.arguments.iter()
+            .filter(|arg| arg.is_required() && arg.is_input() && !arg.is_deprecated() && arg.name != image_input)
+            .collect();
+
+        // libvips checks each value as it is set, so errors point at the right token
+        let mut op = VipsOperation::new(nickname).map_err(|e| self.error(name, e.to_string()))?;
+        let mut step = Step::new(nickname);
+        let mut set = |step: &mut Step, arg: &ArgumentInfo, text: &str, token: &Token| -> Result<(), RecipeError> {
+            op.set_from_string(&arg.name, text)
+                .map_err(|e| self.error(token, format!("bad value for {}: {}", arg.name, e.to_string().trim())))?;
+            step.arguments.push((arg.name.clone(), value(text, arg)));
+            Ok(())
+        };
+
+        let mut n_positional = 0;
+        let mut i = 1;
+        while i < self.tokens.len() {
+            let token = &self.tokens[i];
+            i += 1;
+            if token.text.starts_with("--") {
+                let (option, inline) = match token.text[2..].find('=') {
+                    Some(eq) => (&token.text[2..2 + eq], Some(&token.text[3 + eq..])),
+                    None => (&token.text[2..], None),
+                };
+                let arg = match info.arguments.iter().find(|arg| arg.name == option && arg.is_input() && arg.name != image_input) {
+                    Some(arg) => arg,
+                    None => return Err(self.error(token, format!("{} has no option {}", nickname, option))),
+                };
+                match inline {
+                    Some(text) => set(&mut step, arg, text, token)?,
+                    None if arg.type_name == "gboolean" => set(&mut step, arg, "true", token)?,
+                    None => {
+                        let value = match self.tokens.get(i) {
+                            Some(value) => value,
+                            None => return Err(self.error(token, format!("--{} needs a value", option))),
+                        };
+                        i += 1;
+                        set(&mut step, arg, &value.text, value)?;
+                    },
+                }
+            } else {
+                let arg = match positional.get(n_positional) {
+                    Some(arg) => arg,
+                    None => return Err(self.error(token, format!("{} takes {} positional arguments", nickname, positional.len()))),
+                };
+                n_positional += 1;
+                set(&mut step, arg, &token.text, token)?;
+            }
+        }
+        if n_positional < positional.len() {
+            let missing: Vec<&str> = positional[n_positional..].iter().map(|arg| arg.name.as_str()).collect();
+            return Err(self.error(name, format!("{} needs {}", nickname, missing.join(", "))));
+        }
+        Ok(step)
+    }
+}
+
+/// Parse a recipe into a `Pipeline`. Needs a live `VipsInstance`, since
+/// arguments are checked against the operations libvips provides.
+pub fn parse(recipe: &str) -> Result<Pipeline, RecipeError> {
+    let mut pipeline = Pipeline::new();
+    for (i, line) in recipe.lines().enumerate() {
+        let line_number = i + 1;
+        let tokens = tokenize(strip_comment(line)).map_err(|(column, message)| RecipeError {
+            line: line_number, column, token: "\"".to_string(), message,
+        })?;
+        if tokens.is_empty() {
+            continue;
+        }
+        let parser = LineParser { line: line_number, tokens: &tokens };
+        match tokens[0].text.as_str() {
+            "load" => pipeline.load_options = parser.options("load")?,
+            "save" => pipeline.save_options = parser.options("save")?,
+            _ => pipeline.steps.push(parser.step()?),
+        }
+    }
+    Ok(pipeline)
+}
+
+/// Parse one operation already split into arguments, as a shell does,
+/// e.g. `["gaussblur", "2.0", "--precision", "float"]`.
+///
+/// In errors, `line` is 1 and `column` is the 1-based index of the argument.
+pub fn parse_args<S: AsRef<str>>(args: &[S]) -> Result<Step, RecipeError> {
+    let tokens: Vec<Token> = args.iter().enumerate()
+        .map(|(i, arg)| Token { column: i + 1, text: arg.as_ref().to_string() })
+        .collect();
+    if tokens.is_empty() {
+        return Err(RecipeError { line: 1, column: 1, token: String::new(), message: "no operation given".to_string() });
+    }
+    LineParser { line: 1, tokens: &tokens }.step()
+}
+
+impl Pipeline {
+    // see the `recipe` module
+    pub fn from_recipe(recipe: &str) -> Result<Pipeline, RecipeError> {
+        parse(recipe)
+    }
+}
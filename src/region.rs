@@ -1,17 +1,228 @@
 use ::ffi;
+use ffi::VipsRect;
 use image::VipsImage;
+use pixel::{VipsPixel, check_format};
+use common::current_error;
+use std::error::Error;
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::mem::{size_of, ManuallyDrop};
+use std::ops::Deref;
+use std::slice;
 
-pub struct VipsRegion {
-    pub c: *mut ffi::VipsRegion
+/// A rectangular window onto the pixels of a `VipsImage`.
+///
+/// A region borrows its image, so the image outlives every region made from it.
+/// Only the pixels inside the prepared area are computed, which lets you read
+/// tiles of a huge image without decoding all of it.
+///
+/// libvips ties a region to the thread that created it, so unlike `VipsImage`
+/// it is neither `Send` nor `Sync`. Make one region per thread.
+pub struct VipsRegion<'a> {
+    pub c: *mut ffi::VipsRegion,
+    marker: PhantomData<&'a ()>,
 }
 
-impl VipsRegion {
-    pub fn new(image: &VipsImage) -> VipsRegion {
+impl<'a> Drop for VipsRegion<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::g_object_unref(self.c as *mut c_void);
+        }
+    }
+}
+
+impl<'a> VipsRegion<'a> {
+
+    //
+    // ─── CONSTRUCTORS ───────────────────────────────────────────────────────────────
+    //
+
+    pub fn new<'b>(image: &'a VipsImage<'b>) -> Result<VipsRegion<'a>, Box<Error>> {
         let c = unsafe {
             ffi::vips_region_new(image.c)
         };
+        if c.is_null() {
+            return Err(current_error().into());
+        }
+        Ok(VipsRegion {
+            c,
+            marker: PhantomData,
+        })
+    }
+
+    // takes over a reference the caller already owns
+    pub(crate) unsafe fn from_raw(c: *mut ffi::VipsRegion) -> VipsRegion<'a> {
         VipsRegion {
-            c
+            c,
+            marker: PhantomData,
+        }
+    }
+
+    //
+    // ─── PROPERTIES ─────────────────────────────────────────────────────────────────
+    //
+
+    // the area that currently holds valid pixels
+    pub fn valid(&self) -> VipsRect {
+        unsafe { (*self.c).valid }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.valid().width as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.valid().height as u32
+    }
+
+    pub fn bands(&self) -> u32 {
+        unsafe { (*(*self.c).im).Bands as u32 }
+    }
+
+    pub fn format(&self) -> ffi::VipsBandFormat {
+        unsafe { (*(*self.c).im).BandFmt }
+    }
+
+    // bytes per line of the underlying memory
+    pub fn bytes_per_line(&self) -> usize {
+        unsafe { (*self.c).bpl as usize }
+    }
+
+    // elements of `T` between the starts of two consecutive lines
+    pub fn stride<T: VipsPixel>(&self) -> usize {
+        self.bytes_per_line() / size_of::<T>()
+    }
+
+    //
+    // ─── PIXELS ─────────────────────────────────────────────────────────────────────
+    //
+
+    // compute the pixels of `rect` (clipped to the image)
+    pub fn prepare(&mut self, rect: &VipsRect) -> Result<(), Box<Error>> {
+        let ret = unsafe { ffi::vips_region_prepare(self.c, rect) };
+        result_region(ret)
+    }
+
+    // move the region to (x, y) without recomputing; only for regions attached to memory images
+    pub fn position(&mut self, x: i32, y: i32) -> Result<(), Box<Error>> {
+        let ret = unsafe { ffi::vips_region_position(self.c, x, y) };
+        result_region(ret)
+    }
+
+    /// The valid area as a typed slice.
+    ///
+    /// Rows are `stride::<T>()` elements apart and each row holds `width() * bands()`
+    /// meaningful elements.
+    pub fn buffer<T: VipsPixel>(&self) -> Result<&[T], Box<Error>> {
+        check_format::<T>(self.format())?;
+        let valid = self.valid();
+        let data = unsafe { (*self.c).data };
+        if data.is_null() || valid.width <= 0 || valid.height <= 0 {
+            return Err("region has not been prepared".into());
+        }
+        let row = valid.width as usize * self.bands() as usize;
+        let len = self.stride::<T>() * (valid.height as usize - 1) + row;
+        Ok(unsafe { slice::from_raw_parts(data as *const T, len) })
+    }
+
+    // prepare `rect` and copy its pixels into a tightly packed vec
+    pub fn fetch<T: VipsPixel>(&mut self, rect: &VipsRect) -> Result<Vec<T>, Box<Error>> {
+        self.prepare(rect)?;
+        let valid = self.valid();
+        let stride = self.stride::<T>();
+        let row = valid.width as usize * self.bands() as usize;
+        let buf = self.buffer::<T>()?;
+        let mut out = Vec::with_capacity(row * valid.height as usize);
+        for y in 0..valid.height as usize {
+            out.extend_from_slice(&buf[y * stride..y * stride + row]);
         }
+        Ok(out)
     }
+
+    // fill `rect` with a byte value
+    pub fn paint(&mut self, rect: &VipsRect, value: i32) {
+        unsafe { ffi::vips_region_paint(self.c, rect, value) }
+    }
+
+    // fill the valid area with zero
+    pub fn black(&mut self) {
+        unsafe { ffi::vips_region_black(self.c) }
+    }
+
+    /// Copy `rect` of self to (x, y) of `dest`.
+    ///
+    /// `rect` must lie inside the valid area of self, the target inside the
+    /// valid area of `dest`, and both regions must have the same format and bands.
+    pub fn copy(&self, dest: &mut VipsRegion, rect: &VipsRect, x: i32, y: i32) -> Result<(), Box<Error>> {
+        if (self.format(), self.bands()) != (dest.format(), dest.bands()) {
+            return Err(format!("can not copy {} band {:?} pixels into a {} band {:?} region",
+                self.bands(), self.format(), dest.bands(), dest.format()).into());
+        }
+        let target = VipsRect { left: x, top: y, width: rect.width, height: rect.height };
+        if !contains(&self.valid(), rect) || !contains(&dest.valid(), &target) {
+            return Err("copy is outside the valid area of a region".into());
+        }
+        unsafe { ffi::vips_region_copy(self.c, dest.c, rect, x, y) };
+        Ok(())
+    }
+
+    // 2x2 box shrink of self into `target` of `to`
+    pub fn shrink(&self, to: &mut VipsRegion, target: &VipsRect) -> Result<(), Box<Error>> {
+        let ret = unsafe { ffi::vips_region_shrink(self.c, to.c, target) };
+        result_region(ret)
+    }
+}
+
+/// The output region handed to a `Generator`. Its valid area is the part of
+/// the image libvips wants computed.
+pub struct RegionMut<'a> {
+    region: ManuallyDrop<VipsRegion<'a>>,
+}
+
+impl<'a> RegionMut<'a> {
+    // borrows a region owned by libvips, so it is never unreffed here
+    pub(crate) unsafe fn from_raw(c: *mut ffi::VipsRegion) -> RegionMut<'a> {
+        RegionMut {
+            region: ManuallyDrop::new(VipsRegion::from_raw(c)),
+        }
+    }
+
+    /// The valid area as a mutable typed slice, laid out as in `VipsRegion::buffer`.
+    pub fn buffer_mut<T: VipsPixel>(&mut self) -> Result<&mut [T], Box<Error>> {
+        let len = self.region.buffer::<T>()?.len();
+        let data = unsafe { (*self.region.c).data };
+        Ok(unsafe { slice::from_raw_parts_mut(data as *mut T, len) })
+    }
+
+    pub fn paint(&mut self, rect: &VipsRect, value: i32) {
+        self.region.paint(rect, value)
+    }
+
+    pub fn black(&mut self) {
+        self.region.black()
+    }
+}
+
+impl<'a> Deref for RegionMut<'a> {
+    type Target = VipsRegion<'a>;
+
+    fn deref(&self) -> &VipsRegion<'a> {
+        &self.region
+    }
+}
+
+fn result_region(ret: ::std::os::raw::c_int) -> Result<(), Box<Error>> {
+    match ret {
+        0 => Ok(()),
+        _ => Err(current_error().into()),
+    }
+}
+
+// whether `inner` is a non-empty rect inside `outer`
+fn contains(outer: &VipsRect, inner: &VipsRect) -> bool {
+    let (left, top) = (inner.left as i64, inner.top as i64);
+    inner.width > 0 && inner.height > 0
+        && left >= outer.left as i64 && top >= outer.top as i64
+        && left + inner.width as i64 <= outer.left as i64 + outer.width as i64
+        && top + inner.height as i64 <= outer.top as i64 + outer.height as i64
 }
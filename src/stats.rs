@@ -0,0 +1,51 @@
+use ::ffi;
+use cache;
+
+/// A snapshot of the memory and files libvips is using.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Stats {
+    // bytes currently allocated for pixel buffers
+    pub tracked_mem: usize,
+    // the largest `tracked_mem` seen so far
+    pub tracked_mem_highwater: usize,
+    // number of live pixel buffer allocations
+    pub tracked_allocs: u32,
+    // number of open files
+    pub tracked_files: u32,
+    // operations in the cache
+    pub cache_size: u32,
+    pub cache_max: i32,
+    pub cache_max_mem: usize,
+    pub cache_max_files: i32,
+}
+
+impl Stats {
+    /// The stats as `(name, value)` pairs, named in Prometheus style.
+    pub fn gauges(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("vips_tracked_mem_bytes", self.tracked_mem as f64),
+            ("vips_tracked_mem_highwater_bytes", self.tracked_mem_highwater as f64),
+            ("vips_tracked_allocs", self.tracked_allocs as f64),
+            ("vips_tracked_files", self.tracked_files as f64),
+            ("vips_cache_operations", self.cache_size as f64),
+            ("vips_cache_max_operations", self.cache_max as f64),
+            ("vips_cache_max_mem_bytes", self.cache_max_mem as f64),
+            ("vips_cache_max_files", self.cache_max_files as f64),
+        ]
+    }
+}
+
+pub fn stats() -> Stats {
+    unsafe {
+        Stats {
+            tracked_mem: ffi::vips_tracked_get_mem(),
+            tracked_mem_highwater: ffi::vips_tracked_get_mem_highwater(),
+            tracked_allocs: ffi::vips_tracked_get_allocs() as u32,
+            tracked_files: ffi::vips_tracked_get_files() as u32,
+            cache_size: cache::size(),
+            cache_max: ffi::vips_cache_get_max(),
+            cache_max_mem: ffi::vips_cache_get_max_mem(),
+            cache_max_files: ffi::vips_cache_get_max_files(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "async")]
+extern crate tokio;
+extern crate vips;
+
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::task::{Context, Waker};
+use vips::*;
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn futures_are_send() {
+    assert_send::<VipsFuture<VipsImage<'static>>>();
+    assert_send::<VipsFuture<Vec<u8>>>();
+    assert_send::<AsyncError>();
+}
+
+#[test]
+fn pool() {
+    let _instance = VipsInstance::new("async_test", true).unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let pool = AsyncVips::new(1, 1);
+
+    let pixels: Vec<u8> = (0..32 * 32 * 3).map(|i| i as u8).collect();
+    let img = VipsImage::from_memory(pixels, 32, 32, 3).unwrap();
+    let png = runtime.block_on(pool.write_to_buffer(img, ".png")).unwrap();
+    let decoded = runtime.block_on(pool.from_buffer(png)).unwrap();
+    let small = runtime.block_on(pool.thumbnail(decoded, 8, 8, VipsSize::VIPS_SIZE_FORCE)).unwrap();
+    assert_eq!((small.width(), small.height(), small.bands()), (8, 8, 3));
+    match runtime.block_on(pool.from_buffer(vec![1, 2, 3])) {
+        Err(AsyncError::Vips(_)) => {},
+        other => panic!("expected a libvips error, got {:?}", other.map(|_| ())),
+    }
+
+    // one job running and one queued fill the pool, so a third waits for a slot
+    let mut cx = Context::from_waker(Waker::noop());
+    let (release, wait) = mpsc::channel::<()>();
+    let mut busy = Box::pin(pool.run(move |_| {
+        wait.recv().unwrap();
+        Ok(())
+    }));
+    assert!(busy.as_mut().poll(&mut cx).is_pending());
+    let ran = Arc::new(AtomicBool::new(false));
+    let flag = ran.clone();
+    let mut dropped = Box::pin(pool.run(move |_| {
+        flag.store(true, Ordering::SeqCst);
+        Ok(())
+    }));
+    assert!(dropped.as_mut().poll(&mut cx).is_pending());
+    let mut waiting = Box::pin(pool.run(|_| Ok(3)));
+    assert!(waiting.as_mut().poll(&mut cx).is_pending());
+
+    drop(dropped);
+    release.send(()).unwrap();
+    runtime.block_on(busy).unwrap();
+    assert_eq!(runtime.block_on(waiting), Ok(3));
+    assert!(!ran.load(Ordering::SeqCst));
+
+    let panicking = pool.run::<(), _>(|_| panic!("job panicked"));
+    assert_eq!(runtime.block_on(panicking), Err(AsyncError::Panicked));
+    assert_eq!(runtime.block_on(pool.run(|_| Ok(4))), Ok(4));
+
+    let late = pool.run(|_| Ok(5));
+    pool.shutdown();
+    assert_eq!(runtime.block_on(late), Err(AsyncError::Closed));
+}
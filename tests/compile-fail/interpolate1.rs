@@ -0,0 +1,14 @@
+extern crate vips;
+
+use vips::VipsInstance;
+use vips::VipsInterpolate;
+use vips::VipsInterpolateMethod;
+
+fn main() {
+    let _instance = VipsInstance::new("interpolate_test", true).unwrap();
+    let _method: VipsInterpolateMethod = {
+        let interpolate = VipsInterpolate::new("bilinear").unwrap();
+        interpolate.method()
+        //~^ ERROR `interpolate` does not live long enough
+    };
+}
@@ -0,0 +1,16 @@
+extern crate vips;
+
+use vips::VipsBandFormat;
+use vips::VipsInstance;
+use vips::VipsImage;
+use vips::VipsRegion;
+
+fn main() {
+    let _instance = VipsInstance::new("region_test", true).unwrap();
+    let pixels = vec![0; 256 * 256 * 3];
+    let _region: VipsRegion = {
+        let img: VipsImage = VipsImage::from_memory_reference(&pixels, 256, 256, 3, VipsBandFormat::VIPS_FORMAT_UCHAR).unwrap();
+        VipsRegion::new(&img).unwrap()
+        //~^ ERROR `img` does not live long enough
+    };
+}
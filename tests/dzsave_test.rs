@@ -0,0 +1,14 @@
+#![cfg(feature = "vips-8-10")]
+extern crate vips;
+
+use vips::*;
+
+#[test]
+fn dzsave_buffer() {
+    let _instance = VipsInstance::new("dzsave_test", true).unwrap();
+    let img = VipsImage::from_memory(vec![128u8; 100 * 60], 100, 60, 1).unwrap();
+    let options = DzOptions { tile_size: 64, overlap: 0, ..DzOptions::default() };
+    let zip = img.dzsave_buffer(&options).unwrap();
+    // a zip archive, whatever container the options ask for
+    assert_eq!(&zip[..4], b"PK\x03\x04");
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "image")]
+extern crate image;
+extern crate vips;
+
+use std::convert::TryFrom;
+use image::{DynamicImage, ImageBuffer, Luma, LumaA, Rgb, Rgba};
+use vips::*;
+
+#[test]
+fn round_trip() {
+    let _instance = VipsInstance::new("image_test", true).unwrap();
+
+    let rgb = ImageBuffer::from_fn(7, 5, |x, y| Rgb([x as u8, y as u8, (x * y) as u8]));
+    let img = VipsImage::from_image_buffer(rgb.clone()).unwrap();
+    assert_eq!((img.width(), img.height(), img.bands()), (7, 5, 3));
+    assert_eq!(img.format(), VipsBandFormat::VIPS_FORMAT_UCHAR);
+    assert_eq!(img.to_image_buffer::<Rgb<u8>>().unwrap(), rgb);
+    assert!(img.to_image_buffer::<Rgba<u8>>().is_err());
+    assert!(img.to_image_buffer::<Rgb<u16>>().is_err());
+
+    let grey = ImageBuffer::from_fn(4, 3, |x, y| Luma([(x * 1000 + y) as u16]));
+    let img = VipsImage::try_from(grey.clone()).unwrap();
+    assert_eq!(img.format(), VipsBandFormat::VIPS_FORMAT_USHORT);
+    assert_eq!(img.to_image_buffer::<Luma<u16>>().unwrap(), grey);
+
+    let alpha = ImageBuffer::from_fn(3, 3, |x, y| LumaA([x as u8, y as u8]));
+    let dynamic = DynamicImage::ImageLumaA8(alpha);
+    let img = VipsImage::from_dynamic_image(dynamic.clone()).unwrap();
+    assert_eq!(img.to_dynamic_image().unwrap(), dynamic);
+
+    let float = ImageBuffer::from_fn(2, 2, |x, y| Rgba([x as f32 * 0.5, y as f32, 0.25, 1.0]));
+    let dynamic = DynamicImage::ImageRgba32F(float);
+    let img = VipsImage::try_from(dynamic.clone()).unwrap();
+    assert_eq!(img.format(), VipsBandFormat::VIPS_FORMAT_FLOAT);
+    // libvips sees float sRGB in 0..255, and the samples come back in 0..1
+    assert_eq!(img.get_int("interpretation"), Some(VipsInterpretation::VIPS_INTERPRETATION_sRGB as i32));
+    assert_eq!(img.getpoint(1, 1).unwrap(), vec![127.5, 255.0, 63.75, 255.0]);
+    assert_eq!(img.to_dynamic_image().unwrap(), dynamic);
+
+    let double = TypedImage::<f32>::from_image(img).unwrap().cast::<f64>().unwrap();
+    assert!(double.to_dynamic_image().is_err());
+
+    let empty: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(0, 0);
+    assert!(VipsImage::from_image_buffer(empty.clone()).is_err());
+    assert!(VipsImage::from_dynamic_image(DynamicImage::ImageRgb8(empty)).is_err());
+}
@@ -1,6 +1,12 @@
 extern crate compiletest_rs as compiletest;
 use std::path::PathBuf;
 
+// the libvips version features, passed on so the tests can gate on them as well
+const FEATURES: &[(&str, bool)] = &[
+    ("vips-8-10", cfg!(feature = "vips-8-10")),
+    ("vips-8-13", cfg!(feature = "vips-8-13")),
+];
+
 fn run_mode(mode: &'static str) {
     let mut config = compiletest::Config::default();
 
@@ -8,6 +14,12 @@ fn run_mode(mode: &'static str) {
     config.src_base = PathBuf::from(format!("tests/{}", mode));
     config.link_deps(); // Populate config.target_rustcflags with dependencies on the path
     config.clean_rmeta(); // If your tests import the parent crate, this helps with E0464
+    for &(feature, enabled) in FEATURES {
+        if enabled {
+            let flags = config.target_rustcflags.take().unwrap_or_default();
+            config.target_rustcflags = Some(format!("{} --cfg feature=\"{}\"", flags, feature));
+        }
+    }
 
     compiletest::run_tests(&config);
 }
@@ -0,0 +1,28 @@
+#![cfg(feature = "ndarray")]
+extern crate ndarray;
+extern crate vips;
+
+use ndarray::{Array3, s};
+use vips::*;
+
+#[test]
+fn round_trip() {
+    let _instance = VipsInstance::new("ndarray_test", true).unwrap();
+
+    let array = Array3::from_shape_fn((5, 7, 3), |(y, x, b)| (y * 100 + x * 10 + b) as f32);
+    let img = VipsImage::from_ndarray(array.view()).unwrap();
+    assert_eq!((img.width(), img.height(), img.bands()), (7, 5, 3));
+    assert_eq!(img.format(), VipsBandFormat::VIPS_FORMAT_FLOAT);
+    assert_eq!(img.getpoint(6, 4).unwrap(), vec![460.0, 461.0, 462.0]);
+    assert_eq!(img.to_ndarray::<f32>().unwrap(), array);
+    assert!(img.to_ndarray::<u8>().is_err());
+
+    let cropped = img.crop(1, 1, 3, 2).unwrap();
+    assert_eq!(cropped.to_ndarray::<f32>().unwrap(), array.slice(s![1..3, 1..4, ..]));
+
+    // views that are not contiguous rows of pixels are refused
+    assert!(VipsImage::from_ndarray(array.slice(s![.., ..4, ..])).is_err());
+    assert!(VipsImage::from_ndarray(array.view().reversed_axes()).is_err());
+    let standard = array.slice(s![.., ..4, ..]).as_standard_layout().into_owned();
+    assert_eq!(VipsImage::from_ndarray(standard.view()).unwrap().width(), 4);
+}
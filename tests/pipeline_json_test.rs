@@ -0,0 +1,23 @@
+#![cfg(feature = "json")]
+extern crate vips;
+
+use vips::pipeline::{Pipeline, Step, Value};
+
+#[test]
+fn json_round_trip() {
+    let pipeline = Pipeline::new()
+        .load_options("[access=sequential]")
+        .then(Step::new("autorot"))
+        .then(Step::new("thumbnail_image").arg("width", 300).arg("crop", "attention").arg("linear", false))
+        .then(Step::new("sharpen").arg("sigma", 1.5))
+        .save_options("[Q=80,strip]");
+
+    let json = pipeline.to_json().unwrap();
+    assert_eq!(Pipeline::from_json(&json).unwrap(), pipeline);
+
+    let parsed = Pipeline::from_json(r#"{ "steps": [ { "nickname": "autorot" }, { "nickname": "sharpen", "arguments": [["sigma", 2.0]] } ] }"#).unwrap();
+    assert_eq!(parsed.load_options, None);
+    assert_eq!(parsed.steps[0], Step::new("autorot"));
+    assert_eq!(parsed.steps[1].arguments, vec![("sigma".to_string(), Value::Double(2.0))]);
+    assert!(Pipeline::from_json("{ \"steps\": 3 }").is_err());
+}
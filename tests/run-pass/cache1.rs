@@ -0,0 +1,88 @@
+extern crate vips;
+
+use std::panic;
+use vips::*;
+
+fn grey(value: u8) -> VipsImage<'static> {
+    VipsImage::from_memory(vec![value; 8 * 8], 8, 8, 1).unwrap()
+}
+
+fn invert(img: &VipsImage<'static>, no_cache: bool) -> VipsImage<'static> {
+    let mut op = VipsOperation::new("invert").unwrap();
+    op.set_image("in", img).unwrap();
+    op.set_no_cache(no_cache);
+    op.build().unwrap();
+    op.get_image("out").unwrap()
+}
+
+// a revalidated load replaces the cached one, so later plain loads see the new file too
+#[cfg(feature = "vips-8-13")]
+fn revalidate(path: &str) {
+    let load = |revalidate: bool| {
+        let mut op = VipsOperation::new("pngload").unwrap();
+        op.set_string("filename", path).unwrap();
+        op.set_revalidate(revalidate).unwrap();
+        op.build().unwrap();
+        op.get_image("out").unwrap().getpoint(0, 0).unwrap()
+    };
+    cache::drop_all();
+    grey(30).write_to_file(path).unwrap();
+    assert_eq!(load(false), vec![30.0]);
+    grey(40).write_to_file(path).unwrap();
+    assert_eq!(load(false), vec![30.0]);
+    assert_eq!(load(true), vec![40.0]);
+    assert_eq!(load(false), vec![40.0]);
+
+    let mut op = VipsOperation::new("invert").unwrap();
+    assert!(op.set_revalidate(true).is_err());
+}
+
+fn main() {
+    let _instance = VipsInstance::new("cache_test", true).unwrap();
+
+    cache::set_max(50);
+    assert_eq!(cache::max(), 50);
+    cache::set_max_mem(64 * 1024 * 1024);
+    assert_eq!(cache::max_mem(), 64 * 1024 * 1024);
+    cache::set_max_files(10);
+    assert_eq!(cache::max_files(), 10);
+
+    let img = grey(10);
+    cache::drop_all();
+    assert_eq!(cache::get_size(), 0);
+    invert(&img, true);
+    assert_eq!(cache::size(), 0);
+    invert(&img, false);
+    assert_eq!(cache::size(), 1);
+
+    let inverted = cache::with_cache_disabled(|| {
+        assert_eq!(cache::size(), 0);
+        let nested = cache::with_cache_disabled(|| invert(&img, false));
+        assert_eq!(cache::max(), 0);
+        invert(&nested, false)
+    });
+    assert_eq!(cache::size(), 0);
+    assert_eq!(inverted.getpoint(0, 0).unwrap(), vec![10.0]);
+    assert_eq!(cache::max(), 50);
+    assert!(panic::catch_unwind(|| cache::with_cache_disabled(|| panic!("inside the scope"))).is_err());
+    assert_eq!(cache::max(), 50);
+    cache::with_cache_disabled(|| cache::set_max(20));
+    assert_eq!(cache::max(), 20);
+    cache::set_max(50);
+
+    // a file rewritten on disk is only seen again when the load skips the cache
+    let path = ::std::env::temp_dir().join("vips_cache1.png");
+    let path = path.to_str().unwrap();
+    grey(10).write_to_file(path).unwrap();
+    assert_eq!(VipsImage::from_file(path).unwrap().getpoint(0, 0).unwrap(), vec![10.0]);
+    grey(20).write_to_file(path).unwrap();
+    let no_cache = LoadOptions { no_cache: true, ..LoadOptions::default() };
+    assert_eq!(VipsImage::from_file_with(path, &no_cache).unwrap().getpoint(0, 0).unwrap(), vec![20.0]);
+    let sequential = LoadOptions { access: Some(VipsAccess::VIPS_ACCESS_SEQUENTIAL), no_cache: true, ..LoadOptions::default() };
+    let with_options = format!("{}[fail]", path);
+    assert_eq!(VipsImage::from_file_with(&with_options, &sequential).unwrap().width(), 8);
+    assert!(VipsImage::from_file_with("missing.png", &no_cache).is_err());
+    #[cfg(feature = "vips-8-13")]
+    revalidate(path);
+    ::std::fs::remove_file(path).unwrap();
+}
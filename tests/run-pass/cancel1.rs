@@ -0,0 +1,41 @@
+extern crate vips;
+
+use std::time::Duration;
+use vips::*;
+
+fn main() {
+    let _instance = VipsInstance::new("cancel_test", true).unwrap();
+    let pixels = vec![0; 256 * 256 * 3];
+    let img: VipsImage = VipsImage::from_memory_reference(&pixels, 256, 256, 3, VipsBandFormat::VIPS_FORMAT_UCHAR).unwrap();
+    let thumbnail = img.thumbnail(128, 128, VipsSize::VIPS_SIZE_FORCE).unwrap();
+
+    thumbnail.set_kill(true);
+    assert!(thumbnail.is_killed());
+    thumbnail.set_kill(false);
+    assert!(!thumbnail.is_killed());
+    assert!(thumbnail.to_vec::<u8>().is_ok());
+
+    // a kill stops one evaluation; the next one runs, and later failures are not Cancelled
+    thumbnail.set_kill(true);
+    let err = thumbnail.to_vec::<u8>().unwrap_err();
+    assert!(err.downcast_ref::<Cancelled>().is_some());
+    assert!(!thumbnail.is_killed());
+    assert_eq!(thumbnail.to_vec::<u8>().unwrap().len(), 128 * 128 * 3);
+    let err = thumbnail.write_to_buffer(".no-such-format").unwrap_err();
+    assert!(err.downcast_ref::<Cancelled>().is_none());
+
+    // cancelling a private image leaves the image it was made from usable
+    let token = CancellationToken::default();
+    let private = thumbnail.with_cancellation(&token).unwrap();
+    token.cancel();
+    let err = private.to_vec::<u8>().unwrap_err();
+    assert!(err.downcast_ref::<Cancelled>().is_some());
+    assert!(!thumbnail.is_killed());
+    assert_eq!(thumbnail.to_vec::<u8>().unwrap().len(), 128 * 128 * 3);
+
+    let token = CancellationToken::new();
+    assert!(!token.is_cancelled());
+    token.clone().cancel();
+    assert!(token.is_cancelled());
+    assert!(CancellationToken::with_timeout(Duration::from_secs(0)).is_cancelled());
+}
@@ -0,0 +1,52 @@
+#[macro_use]
+extern crate vips;
+
+use std::panic;
+use vips::*;
+
+fn image(pixels: Vec<u8>, bands: u8) -> VipsImage<'static> {
+    let width = pixels.len() as u32 / bands as u32 / 2;
+    VipsImage::from_memory(pixels, width, 2, bands).unwrap()
+}
+
+fn srgb(img: &VipsImage<'static>) -> VipsImage<'static> {
+    let mut op = VipsOperation::new("copy").unwrap();
+    op.set_image("in", img).unwrap();
+    op.set_int("interpretation", VipsInterpretation::VIPS_INTERPRETATION_sRGB as i32).unwrap();
+    op.build().unwrap();
+    op.get_image("out").unwrap()
+}
+
+fn main() {
+    let _instance = VipsInstance::new("diff_test", true).unwrap();
+
+    let a = image(vec![10, 20, 30, 40], 1);
+    let b = image(vec![10, 22, 26, 40], 1);
+    let report = a.diff(&b).unwrap();
+    assert_eq!(report.max_abs, 4.0);
+    assert_eq!(report.mean_abs, 1.5);
+    assert_eq!(report.mse, vec![5.0]);
+    assert!((report.psnr - 10.0 * (255.0f64 * 255.0 / 5.0).log10()).abs() < 1e-9);
+    assert_eq!(report.mean_de00, None);
+    assert_eq!((report.heatmap.width(), report.heatmap.height(), report.heatmap.bands()), (2, 2, 1));
+    assert!(!report.is_identical());
+
+    let same = a.diff(&a.clone()).unwrap();
+    assert!(same.is_identical());
+    assert!(same.psnr.is_infinite());
+    assert!(a.diff(&image(vec![0; 8], 1)).is_err());
+    assert!(a.diff(&image(vec![0; 8], 2)).is_err());
+
+    let red = srgb(&image(vec![200, 0, 0, 200, 0, 0, 200, 0, 0, 200, 0, 0], 3));
+    let redder = srgb(&image(vec![255, 0, 0, 200, 0, 0, 200, 0, 0, 200, 0, 0], 3));
+    let report = red.diff(&redder).unwrap();
+    assert_eq!(report.mse, vec![55.0 * 55.0 / 4.0, 0.0, 0.0]);
+    assert_eq!(report.mean_de00.map(|de| de > 0.0), Some(true));
+    assert!(report.max_de00.unwrap() >= report.mean_de00.unwrap());
+    assert_eq!(report.heatmap.bands(), 1);
+    assert_eq!(red.diff(&red.clone()).unwrap().max_de00, Some(0.0));
+
+    assert_images_similar!(a, b, 4);
+    assert_images_similar!(&a, &b, 4.0, "with a message for {}", "b");
+    assert!(panic::catch_unwind(|| assert_images_similar!(a, b, 3.5)).is_err());
+}
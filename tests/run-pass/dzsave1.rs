@@ -0,0 +1,36 @@
+extern crate vips;
+
+use std::fs;
+use vips::*;
+
+fn main() {
+    let _instance = VipsInstance::new("dzsave_test", true).unwrap();
+    let img = VipsImage::from_memory(vec![128u8; 100 * 60], 100, 60, 1).unwrap();
+
+    let base = ::std::env::temp_dir().join("vips_dzsave1");
+    let files = ::std::env::temp_dir().join("vips_dzsave1_files");
+    let dzi = ::std::env::temp_dir().join("vips_dzsave1.dzi");
+    let _ = fs::remove_dir_all(&files);
+    let options = DzOptions { tile_size: 64, overlap: 0, ..DzOptions::default() };
+    img.dzsave(base.to_str().unwrap(), &options).unwrap();
+
+    let xml = fs::read_to_string(&dzi).unwrap();
+    assert!(xml.contains("TileSize=\"64\""));
+    assert!(xml.contains("Overlap=\"0\""));
+    assert!(xml.contains("Width=\"100\""));
+    assert!(xml.contains("Height=\"60\""));
+
+    // 100x60 halves down to 1x1 in 8 levels; the full size level is two tiles wide and one high
+    let tile = |level: u32, x: u32, y: u32| files.join(level.to_string()).join(format!("{}_{}.jpeg", x, y));
+    assert!(tile(7, 0, 0).exists());
+    assert!(tile(7, 1, 0).exists());
+    assert!(!tile(7, 0, 1).exists());
+    assert!(tile(0, 0, 0).exists());
+    assert!(!files.join("8").exists());
+
+    let bad = DzOptions { suffix: ".nosuchformat".to_string(), ..DzOptions::default() };
+    assert!(img.dzsave(base.to_str().unwrap(), &bad).is_err());
+
+    fs::remove_dir_all(&files).unwrap();
+    fs::remove_file(&dzi).unwrap();
+}
@@ -0,0 +1,62 @@
+extern crate vips;
+
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use vips::*;
+
+// counts the tiles each thread inverts, adding them up as the threads stop
+struct Invert {
+    tiles: Arc<AtomicUsize>,
+}
+
+impl Generator for Invert {
+    type State = usize;
+
+    fn stop(&self, tiles: usize) {
+        self.tiles.fetch_add(tiles, Ordering::SeqCst);
+    }
+
+    fn generate(&self, out: &mut RegionMut, input: &[VipsRegion], tiles: &mut usize) -> Result<(), Box<Error>> {
+        *tiles += 1;
+        let (width, height) = (out.width() as usize, out.height() as usize);
+        let (in_stride, out_stride) = (input[0].stride::<u8>(), out.stride::<u8>());
+        let src = input[0].buffer::<u8>()?;
+        let dst = out.buffer_mut::<u8>()?;
+        for y in 0..height {
+            for x in 0..width {
+                dst[y * out_stride + x] = 255 - src[y * in_stride + x];
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Unstartable;
+
+impl Generator for Unstartable {
+    type State = ();
+
+    fn start(&self) -> Result<(), Box<Error>> {
+        Err("no state".into())
+    }
+
+    fn generate(&self, _: &mut RegionMut, _: &[VipsRegion], _: &mut ()) -> Result<(), Box<Error>> {
+        Ok(())
+    }
+}
+
+fn main() {
+    let _instance = VipsInstance::new("generate_test", true).unwrap();
+    let pixels: Vec<u8> = (0..64 * 64).map(|i| i as u8).collect();
+    let img: VipsImage = VipsImage::from_memory_reference(&pixels, 64, 64, 1, VipsBandFormat::VIPS_FORMAT_UCHAR).unwrap();
+    let tiles = Arc::new(AtomicUsize::new(0));
+    let inverted = VipsImage::generate(&[&img], Invert { tiles: tiles.clone() }).unwrap();
+    let expected: Vec<u8> = pixels.iter().map(|p| 255 - p).collect();
+    assert_eq!(inverted.to_vec::<u8>().unwrap(), expected);
+    assert!(tiles.load(Ordering::SeqCst) > 0);
+
+    // a generator that can not start fails the evaluation instead of crashing
+    let broken = VipsImage::generate(&[&img], Unstartable).unwrap();
+    assert!(broken.to_vec::<u8>().is_err());
+}
@@ -0,0 +1,88 @@
+extern crate vips;
+
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use vips::*;
+
+// nearest neighbour, written against the region like a real kernel would be
+struct Nearest {
+    dropped: Arc<AtomicBool>,
+}
+
+impl CustomInterpolate for Nearest {
+    fn window_size(&self) -> i32 {
+        1
+    }
+
+    fn interpolate(&self, out: &mut [u8], region: &VipsRegion, x: f64, y: f64) -> Result<(), Box<Error>> {
+        let valid = region.valid();
+        let bands = region.bands() as usize;
+        let offset = (y as i32 - valid.top) as usize * region.stride::<u8>() + (x as i32 - valid.left) as usize * bands;
+        out.copy_from_slice(&region.buffer::<u8>()?[offset..offset + bands]);
+        Ok(())
+    }
+}
+
+impl Drop for Nearest {
+    fn drop(&mut self) {
+        self.dropped.store(true, Ordering::SeqCst);
+    }
+}
+
+struct Constant;
+
+impl CustomInterpolate for Constant {
+    fn window_size(&self) -> i32 {
+        2
+    }
+
+    fn interpolate(&self, out: &mut [u8], region: &VipsRegion, x: f64, y: f64) -> Result<(), Box<Error>> {
+        for byte in out.iter_mut() {
+            *byte = 7;
+        }
+        Ok(())
+    }
+}
+
+struct Failing;
+
+impl CustomInterpolate for Failing {
+    fn window_size(&self) -> i32 {
+        1
+    }
+
+    fn interpolate(&self, _: &mut [u8], _: &VipsRegion, _: f64, _: f64) -> Result<(), Box<Error>> {
+        Err("no pixel here".into())
+    }
+}
+
+fn main() {
+    let instance = VipsInstance::new("interpolate_test", true).unwrap();
+    let dropped = Arc::new(AtomicBool::new(false));
+    {
+        let pixels: Vec<u8> = (0..16 * 16 * 2).map(|i| i as u8).collect();
+        let img = VipsImage::from_memory(pixels, 16, 16, 2).unwrap();
+
+        let constant = VipsInterpolate::custom(Constant).unwrap();
+        assert_eq!(constant.window_size(), 2);
+        assert_eq!(constant.window_offset(), 0);
+        let out = img.affine(2.0, 0.0, 0.0, 2.0, Some(constant)).unwrap();
+        assert_eq!(out.width(), 32);
+        assert!(out.to_vec::<u8>().unwrap()[100..200].iter().all(|&v| v == 7));
+
+        let nearest = VipsInterpolate::custom(Nearest { dropped: dropped.clone() }).unwrap();
+        let ours = img.affine(3.0, 0.0, 0.0, 3.0, Some(nearest)).unwrap();
+        let theirs = img.affine(3.0, 0.0, 0.0, 3.0, Some(VipsInterpolate::nearest_static())).unwrap();
+        assert_eq!(ours.to_vec::<u8>().unwrap(), theirs.to_vec::<u8>().unwrap());
+
+        // failed pixels are black, and the error is left in the libvips error buffer
+        let failing = VipsInterpolate::custom(Failing).unwrap();
+        let out = img.affine(2.0, 0.0, 0.0, 2.0, Some(failing)).unwrap();
+        assert!(out.to_vec::<u8>().unwrap().iter().all(|&v| v == 0));
+        assert!(current_error().contains("no pixel here"));
+    }
+    // the operation cache keeps the interpolator alive until shutdown
+    drop(instance);
+    assert!(dropped.load(Ordering::SeqCst));
+}
@@ -0,0 +1,29 @@
+extern crate vips;
+
+use vips::*;
+
+fn main() {
+    let _instance = VipsInstance::new("interpolate_test", true).unwrap();
+    let pixels: Vec<f32> = (0..8 * 8).map(|i| i as f32).collect();
+    let img = TypedImage::from_memory(pixels, 8, 8, 1).unwrap();
+    let mut region = VipsRegion::new(&img).unwrap();
+    region.prepare(&VipsRect { left: 0, top: 0, width: 8, height: 8 }).unwrap();
+
+    let bilinear = VipsInterpolate::bilinear_static();
+    let method = bilinear.method();
+    let window = method.window(2.5, 3.0);
+    assert_eq!((window.left, window.top, window.width, window.height), (2, 3, 2, 2));
+    assert_eq!(method.call::<f32>(&region, 2.5, 3.0).unwrap(), vec![26.5]);
+
+    let mut out = [0.0f32; 1];
+    method.call_into(&region, &mut out, 4.0, 1.5).unwrap();
+    assert_eq!(out, [16.0]);
+    assert!(method.call_into::<f32>(&region, &mut [], 4.0, 1.5).is_err());
+    assert!(method.call::<u8>(&region, 2.5, 3.0).is_err());
+
+    // the window of (7.5, 0) reaches past the right edge of the region
+    assert!(method.call::<f32>(&region, 7.5, 0.0).is_err());
+    region.prepare(&VipsRect { left: 0, top: 0, width: 4, height: 4 }).unwrap();
+    assert!(method.call::<f32>(&region, 3.5, 1.0).is_err());
+    assert!(method.call::<f32>(&region, 2.5, 1.0).is_ok());
+}
@@ -0,0 +1,37 @@
+extern crate vips;
+
+use vips::*;
+use vips::introspect::ArgumentDefault;
+
+fn main() {
+    let _instance = VipsInstance::new("introspect_test", true).unwrap();
+
+    let interpolators = VipsInterpolate::available();
+    let nearest = interpolators.iter().find(|info| info.nickname == "nearest").unwrap();
+    assert_eq!(nearest.window_size, 1);
+    let bicubic = interpolators.iter().find(|info| info.nickname == "bicubic").unwrap();
+    assert_eq!(bicubic.window_size, 4);
+    assert!(interpolators.iter().all(|info| VipsInterpolate::new(&info.nickname).is_ok()));
+
+    let operations = vips::introspect::operations();
+    assert!(operations.len() > 100);
+    let gaussblur = operations.iter().find(|op| op.nickname == "gaussblur").unwrap();
+    assert!(!gaussblur.description.is_empty());
+    assert!(!gaussblur.is_deprecated());
+
+    let required: Vec<&str> = gaussblur.arguments.iter().filter(|arg| arg.is_required()).map(|arg| arg.name.as_str()).collect();
+    assert_eq!(required, vec!["in", "out", "sigma"]);
+    let input = &gaussblur.arguments[0];
+    assert_eq!(input.type_name, "VipsImage");
+    assert!(input.is_input() && !input.is_output());
+
+    let sigma = gaussblur.arguments.iter().find(|arg| arg.name == "sigma").unwrap();
+    assert_eq!(sigma.type_name, "gdouble");
+    assert!(sigma.min.is_some() && sigma.max.is_some());
+    let precision = gaussblur.arguments.iter().find(|arg| arg.name == "precision").unwrap();
+    assert!(!precision.is_required());
+    assert_eq!(precision.default, Some(ArgumentDefault::Enum("integer".to_string())));
+
+    assert_eq!(vips::introspect::operation("gaussblur").unwrap().arguments.len(), gaussblur.arguments.len());
+    assert!(vips::introspect::operation("no_such_operation").is_err());
+}
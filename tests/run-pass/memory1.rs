@@ -0,0 +1,43 @@
+extern crate vips;
+
+use vips::VipsBandFormat;
+use vips::VipsInstance;
+use vips::VipsImage;
+use vips::MemoryLayoutError;
+
+fn layout_error(result: Result<VipsImage, Box<std::error::Error>>) -> MemoryLayoutError {
+    match result {
+        Ok(_) => panic!("buffer was accepted"),
+        Err(e) => *e.downcast_ref::<MemoryLayoutError>().expect("not a MemoryLayoutError"),
+    }
+}
+
+fn main() {
+    let _instance = VipsInstance::new("memory_test", true).unwrap();
+    let uchar = VipsBandFormat::VIPS_FORMAT_UCHAR;
+    let pixels = vec![0u8; 4 * 4 * 3];
+
+    // a larger buffer than needed is fine
+    assert!(VipsImage::from_memory_reference(&pixels, 4, 4, 1, uchar).is_ok());
+    assert!(VipsImage::from_memory(pixels.clone(), 4, 4, 3).is_ok());
+    // the owned constructor takes its format from the element type and wants an exact fit
+    assert_eq!(VipsImage::from_memory(vec![0.5f32; 4 * 4], 4, 4, 1).unwrap().format(), VipsBandFormat::VIPS_FORMAT_FLOAT);
+    assert!(VipsImage::from_memory(pixels.clone(), 4, 4, 1).is_err());
+
+    assert_eq!(layout_error(VipsImage::from_memory(pixels.clone(), 0, 4, 3)),
+        MemoryLayoutError::Empty { width: 0, height: 4, bands: 3 });
+    assert_eq!(layout_error(VipsImage::from_memory_reference(&pixels, 4, 0, 3, uchar)),
+        MemoryLayoutError::Empty { width: 4, height: 0, bands: 3 });
+    assert_eq!(layout_error(VipsImage::from_memory_reference(&pixels, 4, 4, 0, uchar)),
+        MemoryLayoutError::Empty { width: 4, height: 4, bands: 0 });
+
+    assert_eq!(layout_error(VipsImage::from_memory(pixels.clone(), u32::MAX, 4, 3)),
+        MemoryLayoutError::TooLarge { width: u32::MAX, height: 4, bands: 3 });
+    assert_eq!(layout_error(VipsImage::from_memory_reference(&pixels, 1 << 30, 1 << 30, 255, VipsBandFormat::VIPS_FORMAT_DPCOMPLEX)),
+        MemoryLayoutError::TooLarge { width: 1 << 30, height: 1 << 30, bands: 255 });
+
+    assert_eq!(layout_error(VipsImage::from_memory(pixels.clone(), 5, 4, 3)),
+        MemoryLayoutError::BufferTooSmall { len: 48, needed: 60 });
+    assert_eq!(layout_error(VipsImage::from_memory_reference(&pixels, 4, 4, 3, VipsBandFormat::VIPS_FORMAT_FLOAT)),
+        MemoryLayoutError::BufferTooSmall { len: 48, needed: 192 });
+}
@@ -0,0 +1,58 @@
+#[macro_use]
+extern crate vips;
+
+use std::error::Error;
+use vips::*;
+
+struct AddConst(u8);
+
+impl Generator for AddConst {
+    type State = ();
+
+    fn generate(&self, out: &mut RegionMut, input: &[VipsRegion], _: &mut ()) -> Result<(), Box<Error>> {
+        let (width, height) = (out.width() as usize, out.height() as usize);
+        let (in_stride, out_stride) = (input[0].stride::<u8>(), out.stride::<u8>());
+        let src = input[0].buffer::<u8>()?;
+        let dst = out.buffer_mut::<u8>()?;
+        for y in 0..height {
+            for x in 0..width {
+                dst[y * out_stride + x] = src[y * in_stride + x].saturating_add(self.0);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn add(args: &mut Arguments) -> Result<(), Box<Error>> {
+    let out = {
+        let img = args.image("in")?;
+        VipsImage::generate(&[&img], AddConst(args.int("amount")? as u8))?
+    };
+    args.set_image("out", &out)
+}
+
+vips_operation!(ADD, "rs_add", "add a constant to an image", add, [
+    ("in", "Input image", Image, [VIPS_ARGUMENT_REQUIRED, VIPS_ARGUMENT_INPUT]),
+    ("out", "Output image", Image, [VIPS_ARGUMENT_REQUIRED, VIPS_ARGUMENT_OUTPUT]),
+    ("amount", "Amount to add", Int { min: 0, max: 255, default: 10 }, [VIPS_ARGUMENT_INPUT]),
+]);
+
+fn main() {
+    let _instance = VipsInstance::new("operation_test", true).unwrap();
+    register_operation(&ADD).unwrap();
+    assert!(register_operation(&ADD).is_err());
+
+    let pixels = vec![1u8; 4 * 4];
+    let img: VipsImage = VipsImage::from_memory_reference(&pixels, 4, 4, 1, VipsBandFormat::VIPS_FORMAT_UCHAR).unwrap();
+    let mut op = VipsOperation::new("rs_add").unwrap();
+    op.set_image("in", &img).unwrap();
+    op.set_int("amount", 2).unwrap();
+    assert!(op.set_double("amount", 2.0).is_err());
+    assert!(op.set_int("in", 2).is_err());
+    op.build().unwrap();
+    let out = op.get_image("out").unwrap();
+    assert!(op.get_int("out").is_err());
+    // `add` dropped its own handle on the output, so the operation and `out` own it now
+    drop(op);
+    assert_eq!(out.getpoint(0, 0).unwrap(), vec![3.0]);
+}
@@ -0,0 +1,32 @@
+extern crate vips;
+
+use vips::*;
+
+fn main() {
+    let _instance = VipsInstance::new("pages_test", true).unwrap();
+    let frames: Vec<VipsImage> = (0..3u8).map(|i| {
+        VipsImage::from_memory(vec![i * 50; 8 * 8], 8, 8, 1).unwrap()
+    }).collect();
+
+    let mut animation = VipsImage::from_pages(&frames, &[100, 200, 300]).unwrap();
+    animation.set_loop_count(0).unwrap();
+    assert_eq!(animation.n_pages(), 3);
+    assert_eq!(animation.page_height(), 8);
+    assert_eq!(animation.delay(), Some(vec![100, 200, 300]));
+
+    let pages: Vec<VipsImage> = animation.pages().map(|p| p.unwrap()).collect();
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages[2].getpoint(0, 0).unwrap(), vec![100.0]);
+
+    let thumbnail = animation.thumbnail(4, 4, VipsSize::VIPS_SIZE_FORCE).unwrap();
+    assert_eq!(thumbnail.n_pages(), 3);
+    assert_eq!(thumbnail.height(), 12);
+    assert_eq!(thumbnail.delay(), Some(vec![100, 200, 300]));
+    assert_eq!(thumbnail.loop_count(), Some(0));
+
+    let cropped = animation.crop(2, 2, 4, 4).unwrap();
+    assert_eq!(cropped.n_pages(), 3);
+    assert_eq!(cropped.width(), 4);
+
+    assert!(VipsImage::from_pages(&frames, &[100]).is_err());
+}
@@ -0,0 +1,34 @@
+extern crate vips;
+
+use vips::*;
+use vips::pipeline::{Pipeline, Step};
+
+fn main() {
+    let _instance = VipsInstance::new("pipeline_test", true).unwrap();
+    let pixels: Vec<u8> = (0..16 * 8).map(|i| i as u8).collect();
+    let img = VipsImage::from_memory(pixels, 16, 8, 1).unwrap();
+
+    let pipeline = Pipeline::new()
+        .then(Step::new("invert"))
+        .then(Step::new("flip").arg("direction", "horizontal"))
+        .then(Step::new("embed").arg("x", 1).arg("y", 2).arg("width", 20).arg("height", 12).arg("extend", "white"))
+        .save_options("[Q=80]");
+    assert_eq!(pipeline.to_string(), "load\ninvert\nflip direction=horizontal\nembed x=1 y=2 width=20 height=12 extend=white\nsave [Q=80]");
+    pipeline.validate().unwrap();
+
+    // the recipe can be run on many inputs
+    for _ in 0..3 {
+        let out = pipeline.run(&img).unwrap();
+        assert_eq!((out.width(), out.height()), (20, 12));
+        assert_eq!(out.getpoint(0, 0).unwrap(), vec![255.0]);
+        // (1, 2) is the top right pixel of the input, 15, inverted
+        assert_eq!(out.getpoint(1, 2).unwrap(), vec![240.0]);
+    }
+
+    let invalid = |step: Step| Pipeline::new().then(Step::new("invert")).then(step).validate().unwrap_err().to_string();
+    assert!(invalid(Step::new("no_such_operation")).starts_with("step 2 (no_such_operation)"));
+    assert!(invalid(Step::new("flip")).contains("needs direction"));
+    assert!(invalid(Step::new("flip").arg("direction", "sideways")).contains("direction"));
+    assert!(invalid(Step::new("flip").arg("direction", "horizontal").arg("angle", 90)).contains("has no argument angle"));
+    assert!(invalid(Step::new("black").arg("width", 1).arg("height", 1)).contains("does not take an image"));
+}
@@ -0,0 +1,36 @@
+extern crate vips;
+
+use vips::VipsBandFormat;
+use vips::VipsInstance;
+use vips::VipsImage;
+
+fn main() {
+    let _instance = VipsInstance::new("pixels_test", true).unwrap();
+    let pixels: Vec<u8> = (0..8 * 4 * 3).map(|i| i as u8).collect();
+    let img: VipsImage = VipsImage::from_memory_reference(&pixels, 8, 4, 3, VipsBandFormat::VIPS_FORMAT_UCHAR).unwrap();
+
+    assert_eq!(img.getpoint(1, 2).unwrap(), vec![51.0, 52.0, 53.0]);
+    assert_eq!(img.to_vec::<u8>().unwrap(), pixels);
+    assert!(img.to_vec::<f32>().is_err());
+
+    let view = img.pixels::<u8>().unwrap();
+    assert_eq!(view.pixel(1, 2), &[51, 52, 53]);
+    assert_eq!(view.row(3), &pixels[72..96]);
+
+    // a borrowed buffer starting at an odd address can not be viewed as u16
+    let bytes = vec![0u8; 4 * 4 * 2 + 1];
+    let odd: VipsImage = VipsImage::from_memory_reference(&bytes[1..], 4, 4, 1, VipsBandFormat::VIPS_FORMAT_USHORT).unwrap();
+    assert!(odd.pixels::<u16>().is_err());
+
+    let rows: Vec<Vec<u8>> = img.rows::<u8>().unwrap().map(|r| r.unwrap()).collect();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[1], &pixels[24..48]);
+
+    let tiles: Vec<_> = img.tiles::<u8>(3, 3).unwrap().map(|t| t.unwrap()).collect();
+    assert_eq!(tiles.len(), 6);
+    assert_eq!(tiles[5].0.width, 2);
+    assert_eq!(tiles[5].0.height, 1);
+    let whole: Vec<_> = img.tiles::<u8>(u32::MAX, u32::MAX).unwrap().map(|t| t.unwrap()).collect();
+    assert_eq!(whole.len(), 1);
+    assert_eq!((whole[0].0.width, whole[0].0.height), (img.width() as i32, img.height() as i32));
+}
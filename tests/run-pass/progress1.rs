@@ -0,0 +1,32 @@
+extern crate vips;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use vips::*;
+
+fn main() {
+    let _instance = VipsInstance::new("progress_test", true).unwrap();
+    let pixels = vec![0; 512 * 512 * 3];
+    let img: VipsImage = VipsImage::from_memory_reference(&pixels, 512, 512, 3, VipsBandFormat::VIPS_FORMAT_UCHAR).unwrap();
+    let mut thumbnail = img.thumbnail(300, 300, VipsSize::VIPS_SIZE_FORCE).unwrap();
+    let shared = thumbnail.clone();
+
+    let started = Arc::new(AtomicUsize::new(0));
+    let finished = Arc::new(AtomicUsize::new(0));
+    thumbnail.set_progress(true).unwrap();
+    {
+        let started = started.clone();
+        thumbnail.on_preeval(move |_| { started.fetch_add(1, Ordering::SeqCst); }).unwrap();
+    }
+    {
+        let finished = finished.clone();
+        thumbnail.on_posteval(move |p| { finished.fetch_add(p.tpels as usize, Ordering::SeqCst); }).unwrap();
+    }
+    thumbnail.to_vec::<u8>().unwrap();
+    // the handlers went on a private copy, so the clone and a cached thumbnail do not fire them
+    shared.to_vec::<u8>().unwrap();
+    img.thumbnail(300, 300, VipsSize::VIPS_SIZE_FORCE).unwrap().to_vec::<u8>().unwrap();
+
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+    assert_eq!(finished.load(Ordering::SeqCst), 300 * 300);
+}
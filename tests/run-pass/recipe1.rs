@@ -0,0 +1,65 @@
+extern crate vips;
+
+use vips::*;
+use vips::pipeline::{Step, Value};
+use vips::recipe::{self, RecipeError};
+
+fn error(text: &str) -> RecipeError {
+    recipe::parse(text).unwrap_err()
+}
+
+fn main() {
+    let _instance = VipsInstance::new("recipe_test", true).unwrap();
+
+    let pipeline = Pipeline::from_recipe("
+        # a comment
+        load [access=sequential]
+        thumbnail 30 --height 20 --crop centre
+        embed 1 2 40 30 --extend white   # trailing comment
+        flip horizontal
+        save [Q=80,strip]
+    ").unwrap();
+    assert_eq!(pipeline.load_options, Some("[access=sequential]".to_string()));
+    assert_eq!(pipeline.save_options, Some("[Q=80,strip]".to_string()));
+    assert_eq!(pipeline.steps[0], Step::new("thumbnail_image").arg("width", 30).arg("height", 20).arg("crop", "centre"));
+    assert_eq!(pipeline.steps[1].arguments[4], ("extend".to_string(), Value::String("white".to_string())));
+    assert_eq!(pipeline.steps[2], Step::new("flip").arg("direction", "horizontal"));
+
+    let pixels: Vec<u8> = (0..64 * 64).map(|i| i as u8).collect();
+    let img = VipsImage::from_memory(pixels, 64, 64, 1).unwrap();
+    let out = pipeline.run(&img).unwrap();
+    assert_eq!((out.width(), out.height()), (40, 30));
+
+    let linear = recipe::parse("thumbnail 30 --linear\nthumbnail 30 --linear=false").unwrap();
+    assert_eq!(linear.steps[0].arguments[1], ("linear".to_string(), Value::Bool(true)));
+    assert_eq!(linear.steps[1].arguments[1], ("linear".to_string(), Value::Bool(false)));
+    assert_eq!(recipe::parse("gamma --exponent \"2.2\"").unwrap().steps[0].arguments[0].1, Value::Double(2.2));
+    // literals that would not print back the same are kept as written
+    assert_eq!(recipe::parse("thumbnail 030").unwrap().steps[0].arguments[0].1, Value::String("030".to_string()));
+    assert_eq!(recipe::parse("gamma --exponent 1e0").unwrap().steps[0].arguments[0].1, Value::String("1e0".to_string()));
+
+    let e = error("autorot\nthumbnial 300");
+    assert_eq!((e.line, e.column, e.token.as_str()), (2, 1, "thumbnial"));
+    let e = error("thumbnail 300 --hieght 200");
+    assert_eq!((e.line, e.column, e.token.as_str()), (1, 15, "--hieght"));
+    let e = error("thumbnail 300 --crop sideways");
+    assert_eq!((e.column, e.token.as_str()), (22, "sideways"));
+    let e = error("thumbnail 300 --height");
+    assert_eq!(e.token, "--height");
+    let e = error("thumbnail 300 400");
+    assert_eq!((e.column, e.token.as_str()), (15, "400"));
+    let e = error("  embed 1 2");
+    assert_eq!((e.column, e.token.as_str()), (3, "embed"));
+    assert!(e.message.contains("width, height"));
+    let e = error("black 10 10");
+    assert_eq!(e.token, "black");
+    let e = error("save Q=80");
+    assert_eq!((e.column, e.token.as_str()), (6, "Q=80"));
+    let e = error("flip \"hori#zontal\" # a # inside quotes is not a comment");
+    assert_eq!(e.token, "hori#zontal");
+    let e = error("flip \"horizontal");
+    assert_eq!(e.column, 6);
+    assert!(e.to_string().starts_with("line 1, column 6: "));
+    // columns count characters, not bytes
+    assert_eq!(error("text \"ünïcode\" \"open").column, 16);
+}
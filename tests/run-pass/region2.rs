@@ -0,0 +1,32 @@
+extern crate vips;
+
+use vips::VipsBandFormat;
+use vips::VipsInstance;
+use vips::VipsImage;
+use vips::VipsRect;
+use vips::VipsRegion;
+
+fn main() {
+    let _instance = VipsInstance::new("region_test", true).unwrap();
+    let pixels: Vec<u8> = (0..16 * 16).map(|i| i as u8).collect();
+    let img: VipsImage = VipsImage::from_memory_reference(&pixels, 16, 16, 1, VipsBandFormat::VIPS_FORMAT_UCHAR).unwrap();
+    let mut region = VipsRegion::new(&img).unwrap();
+    let tile = region.fetch::<u8>(&VipsRect { left: 4, top: 2, width: 3, height: 2 }).unwrap();
+    assert_eq!(tile, vec![36, 37, 38, 52, 53, 54]);
+    assert!(region.buffer::<u16>().is_err());
+
+    let blank = VipsImage::from_memory(vec![0u8; 8 * 8], 8, 8, 1).unwrap();
+    let mut dest = VipsRegion::new(&blank).unwrap();
+    dest.prepare(&VipsRect { left: 0, top: 0, width: 8, height: 8 }).unwrap();
+    region.prepare(&VipsRect { left: 0, top: 0, width: 16, height: 16 }).unwrap();
+    region.copy(&mut dest, &VipsRect { left: 4, top: 2, width: 3, height: 2 }, 1, 1).unwrap();
+    assert_eq!(dest.fetch::<u8>(&VipsRect { left: 1, top: 1, width: 3, height: 2 }).unwrap(), tile);
+    // past the edge of dest, then past the edge of the source
+    assert!(region.copy(&mut dest, &VipsRect { left: 4, top: 2, width: 3, height: 2 }, 6, 6).is_err());
+    assert!(region.copy(&mut dest, &VipsRect { left: 14, top: 0, width: 4, height: 1 }, 0, 0).is_err());
+
+    let wide = VipsImage::from_memory(vec![0u16; 8 * 8], 8, 8, 1).unwrap();
+    let mut wide_region = VipsRegion::new(&wide).unwrap();
+    wide_region.prepare(&VipsRect { left: 0, top: 0, width: 8, height: 8 }).unwrap();
+    assert!(region.copy(&mut wide_region, &VipsRect { left: 0, top: 0, width: 2, height: 2 }, 0, 0).is_err());
+}
@@ -0,0 +1,42 @@
+extern crate vips;
+
+use vips::*;
+
+fn invert(img: &VipsImage<'static>, no_cache: bool) -> VipsImage<'static> {
+    let mut op = VipsOperation::new("invert").unwrap();
+    op.set_image("in", img).unwrap();
+    op.set_no_cache(no_cache);
+    op.build().unwrap();
+    op.get_image("out").unwrap()
+}
+
+const SIZE: usize = 256 * 256 * 3;
+
+fn main() {
+    let _instance = VipsInstance::new("stats_test", true).unwrap();
+    let img = VipsImage::from_memory(vec![7u8; SIZE], 256, 256, 3).unwrap();
+    cache::drop_all();
+    let inverted = invert(&img, true);
+    let before = stats();
+    assert_eq!(before.cache_size, 0);
+    assert_eq!(before.cache_max, cache::max());
+
+    // rendering a pipeline into memory allocates a buffer tracked by libvips
+    let copy = inverted.copy_memory().unwrap();
+    let allocated = stats();
+    assert!(allocated.tracked_mem >= before.tracked_mem + SIZE);
+    assert!(allocated.tracked_allocs > before.tracked_allocs);
+    assert!(allocated.tracked_mem_highwater >= allocated.tracked_mem);
+    drop(copy);
+    assert!(stats().tracked_mem <= allocated.tracked_mem - SIZE);
+
+    invert(&img, false);
+    let cached = stats();
+    assert_eq!(cached.cache_size, before.cache_size + 1);
+    assert_eq!(cached.cache_size, cache::size());
+    assert!(cached.gauges().contains(&("vips_cache_operations", cached.cache_size as f64)));
+
+    cache::drop_all();
+    assert_eq!(cache::size(), 0);
+    assert_eq!(stats().cache_size, 0);
+}
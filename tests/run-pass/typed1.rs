@@ -0,0 +1,23 @@
+extern crate vips;
+
+use vips::VipsBandFormat;
+use vips::VipsInstance;
+use vips::TypedImage;
+
+fn main() {
+    let _instance = VipsInstance::new("typed_test", true).unwrap();
+    let pixels: Vec<f32> = (0..4 * 2 * 2).map(|i| i as f32).collect();
+    let img: TypedImage<f32> = TypedImage::from_memory(pixels.clone(), 4, 2, 2).unwrap();
+
+    assert_eq!(img.format(), VipsBandFormat::VIPS_FORMAT_FLOAT);
+    assert_eq!(img.getpoint(1, 1).unwrap(), vec![10.0, 11.0]);
+    assert_eq!(img.to_vec().unwrap(), pixels);
+
+    let bytes: TypedImage<u8> = img.cast().unwrap();
+    assert_eq!(bytes.format(), VipsBandFormat::VIPS_FORMAT_UCHAR);
+    assert_eq!(bytes.getpoint(3, 1).unwrap(), vec![14, 15]);
+
+    assert!(TypedImage::<f32>::from_memory(vec![0.0; 15], 4, 2, 2).is_err());
+    assert!(TypedImage::<f32>::from_memory(vec![], 0, 2, 2).is_err());
+    assert!(TypedImage::<u8>::from_image(img.clone().into_inner()).is_err());
+}
@@ -0,0 +1,57 @@
+extern crate vips;
+
+use std::sync::Arc;
+use std::thread;
+use vips::*;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn images_are_send_and_sync() {
+    assert_send_sync::<VipsImage>();
+    assert_send_sync::<VipsInterpolate>();
+}
+
+#[test]
+fn process_across_threads() {
+    let _instance = VipsInstance::new("thread_test", true).unwrap();
+    let pixels: Vec<u8> = (0..64 * 64 * 3).map(|i| i as u8).collect();
+    let img: Arc<VipsImage<'static>> = Arc::new(VipsImage::from_memory(pixels, 64, 64, 3).unwrap());
+
+    let workers: Vec<_> = (1..5).map(|i| {
+        let img = img.clone();
+        thread::spawn(move || {
+            let size = i * 16;
+            let thumbnail = img.thumbnail(size, size, VipsSize::VIPS_SIZE_FORCE).unwrap();
+            let result = (thumbnail.width(), thumbnail.to_vec::<u8>().unwrap().len());
+            drop(thumbnail);
+            thread_shutdown();
+            result
+        })
+    }).collect();
+
+    for (i, worker) in (1..5).zip(workers) {
+        let (width, len) = worker.join().unwrap();
+        assert_eq!(width, i * 16);
+        assert_eq!(len, (i * 16 * i * 16 * 3) as usize);
+    }
+
+    // views of a lazy image taken while other threads evaluate the same image
+    let pipeline = Arc::new(img.thumbnail(32, 32, VipsSize::VIPS_SIZE_FORCE).unwrap());
+    let expected = pipeline.to_vec::<u8>().unwrap();
+    let workers: Vec<_> = (0..8).map(|i| {
+        let pipeline = pipeline.clone();
+        thread::spawn(move || {
+            let pixels = if i % 2 == 0 {
+                pipeline.pixels::<u8>().unwrap().as_slice().to_vec()
+            } else {
+                pipeline.to_vec::<u8>().unwrap()
+            };
+            thread_shutdown();
+            pixels
+        })
+    }).collect();
+    for worker in workers {
+        assert_eq!(worker.join().unwrap(), expected);
+    }
+}
@@ -0,0 +1,13 @@
+use std::process::Command;
+
+fn vipsrs(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_vipsrs")).args(args).output().unwrap().status.code()
+}
+
+#[test]
+fn exit_status() {
+    assert_eq!(vipsrs(&["--help"]), Some(0));
+    assert_eq!(vipsrs(&["header"]), Some(2));
+    assert_eq!(vipsrs(&["batch", "only.recipe"]), Some(2));
+    assert_eq!(vipsrs(&["header", "missing.png"]), Some(1));
+}